@@ -0,0 +1,112 @@
+// Benchmarks del rasterizador: `triangle()` con triángulos de distintos
+// tamaños y `render` completo sobre smooth_sphere.obj. El throughput se
+// informa en fragmentos por segundo.
+//
+//     cargo bench --bench rasterizer
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use nalgebra_glm::{Vec2, Vec3};
+use std::hint::black_box;
+
+use solar_system::color::Color;
+use solar_system::framebuffer::Framebuffer;
+use solar_system::light::Light;
+use solar_system::obj::Obj;
+use solar_system::planet::PlanetType;
+use solar_system::transform;
+use solar_system::triangle::triangle;
+use solar_system::vertex::Vertex;
+use solar_system::{noise_for, render, DepthBias, RenderScratch, ShadingMode, Uniforms};
+
+const WIDTH: usize = 800;
+const HEIGHT: usize = 600;
+const SPHERE_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/models/smooth_sphere.obj");
+
+// Vértice ya transformado a coordenadas de pantalla
+fn screen_vertex(x: f32, y: f32) -> Vertex {
+    let mut vertex = Vertex::new(Vec3::new(x, y, 0.0), Vec3::new(0.0, 0.0, 1.0), Vec2::new(0.0, 0.0));
+    vertex.transformed_position = Vec3::new(x, y, 0.5);
+    vertex
+}
+
+fn bench_triangle(c: &mut Criterion) {
+    let mut group = c.benchmark_group("triangle");
+
+    // Catetos en píxeles: un triángulo de una luna lejana y uno que cubre
+    // buena parte de la pantalla, como el sol de cerca
+    for (name, size) in [("small", 8.0), ("medium", 64.0), ("large", 500.0)] {
+        let (x, y) = (100.0, 50.0);
+        let v1 = screen_vertex(x, y);
+        let v2 = screen_vertex(x, y + size);
+        let v3 = screen_vertex(x + size, y);
+
+        let mut fragments = Vec::new();
+        for coverage_aa in [false, true] {
+            fragments.clear();
+            triangle(&v1, &v2, &v3, WIDTH, HEIGHT, coverage_aa, &mut fragments);
+            group.throughput(Throughput::Elements(fragments.len() as u64));
+            let id = BenchmarkId::new(if coverage_aa { "coverage_aa" } else { "center" }, name);
+            group.bench_with_input(id, &coverage_aa, |b, &coverage_aa| {
+                b.iter(|| {
+                    fragments.clear();
+                    triangle(black_box(&v1), black_box(&v2), black_box(&v3), WIDTH, HEIGHT, coverage_aa, &mut fragments);
+                })
+            });
+        }
+    }
+
+    group.finish();
+}
+
+fn uniforms_for(planet_type: &PlanetType, scale: f32) -> Uniforms {
+    Uniforms {
+        model_matrix: transform::model(Vec3::new(0.0, 0.0, 0.0), scale, Vec3::new(0.0, 0.0, 0.0)),
+        view_matrix: transform::view(Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0)),
+        projection_matrix: transform::perspective(75.0, WIDTH as f32, HEIGHT as f32, 0.1, 1000.0),
+        viewport_matrix: transform::viewport(WIDTH as f32, HEIGHT as f32),
+        time: 0,
+        noise: noise_for(planet_type, 0),
+        cull_backfaces: true,
+        toon_shading: false,
+        show_normals: false,
+        coverage_aa: false,
+        shading_mode: ShadingMode::Phong,
+        depth_bias: DepthBias::default(),
+        show_orbits: false,
+        seed: 0,
+        earth_texture: None,
+        lights: vec![Light::new(Vec3::new(-20.0, 10.0, 20.0), Color::new(255, 245, 225), 1.0)],
+        camera_position: Vec3::new(0.0, 0.0, 5.0),
+    }
+}
+
+fn bench_render(c: &mut Criterion) {
+    let sphere = Obj::load(SPHERE_PATH, false).expect("smooth_sphere.obj").get_vertex_array();
+    let mut framebuffer = Framebuffer::new(WIDTH, HEIGHT);
+    let mut scratch = RenderScratch::default();
+    let mut group = c.benchmark_group("render");
+    group.sample_size(20);
+
+    // Una luna pequeña en pantalla y un sol que casi la llena
+    for (name, planet_type, scale) in [("moon", PlanetType::Moon, 0.3), ("sun", PlanetType::Sun, 3.0)] {
+        let uniforms = uniforms_for(&planet_type, scale);
+
+        // Fragmentos visibles: los píxeles con profundidad tras un render
+        framebuffer.clear();
+        render(&mut framebuffer, &uniforms, &sphere, &planet_type, &mut scratch);
+        let covered = framebuffer.depth_buffer().iter().filter(|depth| depth.is_finite()).count();
+        group.throughput(Throughput::Elements(covered as u64));
+
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                framebuffer.clear();
+                render(&mut framebuffer, black_box(&uniforms), &sphere, &planet_type, &mut scratch);
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_triangle, bench_render);
+criterion_main!(benches);
@@ -1,11 +1,70 @@
-use nalgebra_glm::{Vec3, rotate_vec3};
+use nalgebra_glm::{Vec3, Vec4, Mat4, rotate_vec3};
+use crate::transform;
 use std::f32::consts::PI;
 
+// Punto de vista fijo al que se puede saltar con la cámara
+#[derive(Debug, Clone, Copy)]
+pub struct CameraPreset {
+  pub eye: Vec3,
+  pub center: Vec3,
+  pub up: Vec3,
+}
+
+impl CameraPreset {
+  // Vista de pájaro: directamente sobre el sistema solar, mirando hacia abajo
+  pub fn bird_eye() -> Self {
+    CameraPreset {
+      eye: Vec3::new(0.0, 20.0, 0.0), // Ajusta la altura y posición
+      center: Vec3::new(0.0, 0.0, 0.0), // Mira hacia el centro del sistema
+      up: Vec3::new(0.0, 0.0, 1.0), // Ajusta el vector "up" si es necesario
+    }
+  }
+
+  // Vistas canónicas que se recorren con la tecla C
+  pub fn defaults() -> Vec<CameraPreset> {
+    vec![
+      // Vista inicial, frente al sol
+      CameraPreset {
+        eye: Vec3::new(0.0, 0.0, 5.0),
+        center: Vec3::new(0.0, 0.0, 0.0),
+        up: Vec3::new(0.0, 1.0, 0.0),
+      },
+      CameraPreset::bird_eye(),
+      // De lado, a la altura del plano de las órbitas
+      CameraPreset {
+        eye: Vec3::new(0.0, 3.0, 60.0),
+        center: Vec3::new(0.0, 0.0, 0.0),
+        up: Vec3::new(0.0, 1.0, 0.0),
+      },
+      // En diagonal desde arriba para ver todo el sistema
+      CameraPreset {
+        eye: Vec3::new(40.0, 35.0, 40.0),
+        center: Vec3::new(0.0, 0.0, 0.0),
+        up: Vec3::new(0.0, 1.0, 0.0),
+      },
+    ]
+  }
+}
+
+// Velocidad por defecto con la que la cámara alcanza su objetivo (1/s)
+const DEFAULT_SMOOTHING: f32 = 12.0;
+
+// `eye`, `center` y `up` son la posición que se dibuja; los movimientos solo
+// cambian el objetivo y `update` acerca la cámara a él cada frame
 pub struct Camera {
   pub eye: Vec3,
   pub center: Vec3,
   pub up: Vec3,
-  pub has_changed: bool
+  pub has_changed: bool,
+  // 0 salta directo al objetivo; valores mayores lo alcanzan más rápido
+  pub smoothing: f32,
+  target_eye: Vec3,
+  target_center: Vec3,
+  target_up: Vec3,
+  // `up` tal como lo dio el preset o `look_at`. `target_up` se recalcula a
+  // partir de él tras cada giro, así los errores de redondeo no se acumulan
+  // y el horizonte no se inclina; también es la referencia del límite de pitch.
+  reference_up: Vec3,
 }
 
 impl Camera {
@@ -15,7 +74,83 @@ impl Camera {
       center,
       up,
       has_changed: true,
+      smoothing: DEFAULT_SMOOTHING,
+      target_eye: eye,
+      target_center: center,
+      target_up: up,
+      reference_up: up,
+    }
+  }
+
+  // Coloca la cámara de inmediato, sin la transición suave de `apply_preset`
+  pub fn look_at(&mut self, eye: Vec3, center: Vec3, up: Vec3) {
+    self.target_eye = eye;
+    self.target_center = center;
+    self.target_up = up;
+    self.reference_up = up;
+    self.reorthonormalize();
+
+    self.eye = eye;
+    self.center = center;
+    self.up = self.target_up;
+    self.has_changed = true;
+  }
+
+  // Rehace la base del objetivo conservando la dirección de vista: derecha =
+  // adelante × up de referencia y up = derecha × adelante, ambos unitarios.
+  // Si se mira justo a lo largo del up de referencia se usa el up actual.
+  pub fn reorthonormalize(&mut self) {
+    let forward = self.target_center - self.target_eye;
+    if forward.magnitude() < 1e-6 {
+      return;
+    }
+    let forward = forward.normalize();
+
+    let mut right = forward.cross(&self.reference_up);
+    if right.magnitude() < 1e-6 {
+      right = forward.cross(&self.target_up);
+    }
+    if right.magnitude() < 1e-6 {
+      return;
+    }
+    self.target_up = right.normalize().cross(&forward).normalize();
+  }
+
+  // Acerca la cámara a su objetivo de forma exponencial, independiente del framerate
+  pub fn update(&mut self, delta_time: f32) {
+    let t = if self.smoothing <= 0.0 {
+      1.0
+    } else {
+      1.0 - (-self.smoothing * delta_time).exp()
+    };
+
+    let previous_eye = self.eye;
+    let previous_center = self.center;
+    let previous_up = self.up;
+
+    self.eye += (self.target_eye - self.eye) * t;
+    self.center += (self.target_center - self.center) * t;
+
+    // Mantener `up` unitario y sin quedar paralelo a la dirección de vista;
+    // si la interpolación lo deja casi paralelo se usa directamente el objetivo
+    let up = (self.up + (self.target_up - self.up) * t).normalize();
+    let forward = (self.center - self.eye).normalize();
+    self.up = if up.dot(&forward).abs() > 0.999 { self.target_up } else { up };
+
+    if (self.target_eye - self.eye).magnitude() < 1e-4 && (self.target_center - self.center).magnitude() < 1e-4 {
+      self.eye = self.target_eye;
+      self.center = self.target_center;
+      self.up = self.target_up;
     }
+
+    if self.eye != previous_eye || self.center != previous_center || self.up != previous_up {
+      self.has_changed = true;
+    }
+  }
+
+  // Matriz de vista desde la posición actual (no el objetivo)
+  pub fn view_matrix(&self) -> Mat4 {
+    transform::view(self.eye, self.center, self.up)
   }
 
   pub fn basis_change(&self, vector: &Vec3) -> Vec3 {
@@ -32,7 +167,7 @@ impl Camera {
   }
 
   pub fn orbit(&mut self, delta_yaw: f32, delta_pitch: f32) {
-    let radius_vector = self.eye - self.center;
+    let radius_vector = self.target_eye - self.target_center;
     let radius = radius_vector.magnitude();
 
     let current_yaw = radius_vector.z.atan2(radius_vector.x);
@@ -43,25 +178,45 @@ impl Camera {
     let new_yaw = (current_yaw + delta_yaw) % (2.0 * PI);
     let new_pitch = (current_pitch + delta_pitch).clamp(-PI / 2.0 + 0.1, PI / 2.0 - 0.1);
 
-    let new_eye = self.center + Vec3::new(
+    let new_eye = self.target_center + Vec3::new(
       radius * new_yaw.cos() * new_pitch.cos(),
       -radius * new_pitch.sin(),
       radius * new_yaw.sin() * new_pitch.cos()
     );
 
-    self.eye = new_eye;
+    self.target_eye = new_eye;
+    self.reorthonormalize();
     self.has_changed = true;
   }
 
   pub fn zoom(&mut self, delta: f32) {
-    let direction = (self.center - self.eye).normalize();
-    self.eye += direction * delta;
+    let direction = (self.target_center - self.target_eye).normalize();
+    self.target_eye += direction * delta;
     self.has_changed = true;
   }
 
   pub fn move_center(&mut self, movement: Vec3) {
-    self.center += movement;
-    self.eye += movement;
+    self.target_center += movement;
+    self.target_eye += movement;
+  }
+
+  // Movimiento en el marco de la cámara (x derecha, y arriba, -z adelante),
+  // como el de WASD. basis_change normaliza, así que se recupera la longitud
+  pub fn move_local(&mut self, movement: Vec3) {
+    let length = movement.magnitude();
+    if length > 0.0 {
+      self.move_center(self.basis_change(&movement) * length);
+    }
+  }
+
+  // Centra la cámara en `point` trasladando también el ojo, así la distancia
+  // y el ángulo de vista no cambian mientras el punto se mueve
+  pub fn follow(&mut self, point: Vec3) {
+    let offset = point - self.target_center;
+    if offset.magnitude() > 0.0 {
+      self.move_center(offset);
+      self.has_changed = true;
+    }
   }
 
   pub fn check_if_changed(&mut self) -> bool {
@@ -74,21 +229,284 @@ impl Camera {
   }
 
   pub fn rotate_pitch(&mut self, angle: f32) {
-    // Implementar rotación en el eje X
+    let forward = self.target_center - self.target_eye;
+    let right = forward.cross(&self.target_up).normalize();
+
+    // Pitch positivo mira hacia abajo, igual que en orbit
+    let current_pitch = -(forward.normalize().dot(&self.reference_up.normalize())).clamp(-1.0, 1.0).asin();
+    let new_pitch = (current_pitch + angle).clamp(-PI / 2.0 + 0.1, PI / 2.0 - 0.1);
+
+    // Rotar el centro alrededor del ojo usando el eje derecho de la cámara
+    let rotated = rotate_vec3(&forward, -(new_pitch - current_pitch), &right);
+
+    self.target_center = self.target_eye + rotated;
+    self.reorthonormalize();
+    self.has_changed = true;
   }
 
   pub fn rotate_yaw(&mut self, angle: f32) {
-    // Implementar rotación en el eje Y
+    let forward = self.target_center - self.target_eye;
+    let world_up = Vec3::new(0.0, 1.0, 0.0);
+
+    // Rotar el centro alrededor del ojo usando el eje vertical del mundo
+    let rotated = rotate_vec3(&forward, angle, &world_up);
+
+    self.target_center = self.target_eye + rotated;
+    self.reorthonormalize();
+    self.has_changed = true;
   }
 
   pub fn move_up(&mut self, amount: f32) {
-    self.eye.y += amount;
-    self.center.y += amount;
+    self.target_eye.y += amount;
+    self.target_center.y += amount;
   }
 
   pub fn set_bird_eye_view(&mut self) {
-    self.eye = Vec3::new(0.0, 20.0, 0.0); // Ajusta la altura y posición
-    self.center = Vec3::new(0.0, 0.0, 0.0); // Mira hacia el centro del sistema
-    self.up = Vec3::new(0.0, 0.0, 1.0); // Ajusta el vector "up" si es necesario
+    self.apply_preset(&CameraPreset::bird_eye());
+  }
+
+  pub fn apply_preset(&mut self, preset: &CameraPreset) {
+    self.target_eye = preset.eye;
+    self.target_center = preset.center;
+    self.target_up = preset.up;
+    self.reference_up = preset.up;
+    self.reorthonormalize();
+    self.has_changed = true;
+  }
+}
+
+// Planos del frustum (izquierdo, derecho, abajo, arriba, cercano, lejano)
+// extraídos de projection * view. Cada plano es (normal, d) con la normal
+// unitaria apuntando hacia adentro.
+pub fn frustum_planes(view_projection: &Mat4) -> [Vec4; 6] {
+  let row = |i: usize| {
+    let r = view_projection.row(i);
+    Vec4::new(r[0], r[1], r[2], r[3])
+  };
+  let (row0, row1, row2, row3) = (row(0), row(1), row(2), row(3));
+
+  let mut planes = [
+    row3 + row0,
+    row3 - row0,
+    row3 + row1,
+    row3 - row1,
+    row3 + row2,
+    row3 - row2,
+  ];
+  for plane in planes.iter_mut() {
+    let length = Vec3::new(plane.x, plane.y, plane.z).magnitude();
+    if length > 0.0 {
+      *plane /= length;
+    }
+  }
+  planes
+}
+
+// Falso solo si la esfera queda completamente fuera de algún plano
+pub fn sphere_in_frustum(planes: &[Vec4; 6], center: &Vec3, radius: f32) -> bool {
+  planes.iter().all(|plane| {
+    plane.x * center.x + plane.y * center.y + plane.z * center.z + plane.w >= -radius
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use rand::{Rng, SeedableRng};
+  use rand::rngs::StdRng;
+
+  // Adelante, derecha y up de la cámara ya posicionada (sin suavizado)
+  fn basis(camera: &Camera) -> (Vec3, Vec3, Vec3) {
+    let forward = (camera.center - camera.eye).normalize();
+    let right = forward.cross(&camera.up);
+    (forward, right, camera.up)
+  }
+
+  fn assert_orthonormal(camera: &Camera) {
+    let (forward, right, up) = basis(camera);
+    assert!((up.magnitude() - 1.0).abs() < 1e-4, "|up| = {}", up.magnitude());
+    assert!((right.magnitude() - 1.0).abs() < 1e-4, "|right| = {}", right.magnitude());
+    assert!(forward.dot(&up).abs() < 1e-4, "forward · up = {}", forward.dot(&up));
+  }
+
+  #[test]
+  fn basis_stays_orthonormal_after_random_orbits() {
+    let mut camera = Camera::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+    camera.smoothing = 0.0;
+    let mut rng = StdRng::seed_from_u64(7);
+
+    for _ in 0..10_000 {
+      camera.orbit(rng.gen_range(-0.5..0.5), rng.gen_range(-0.5..0.5));
+      camera.update(1.0 / 60.0);
+      assert_orthonormal(&camera);
+    }
+  }
+
+  // Pasar de largo el polo debe dejar el pitch en el límite, con el up del
+  // mismo lado que el de referencia, tanto con el up del mundo como con el de
+  // la vista de pájaro
+  #[test]
+  fn pitch_past_the_pole_clamps_without_flipping() {
+    let max_pitch = PI / 2.0 - 0.1;
+    for preset in [CameraPreset::defaults()[0], CameraPreset::defaults()[2], CameraPreset::bird_eye()] {
+      let mut camera = Camera::new(preset.eye, preset.center, preset.up);
+      camera.smoothing = 0.0;
+      camera.apply_preset(&preset);
+      camera.update(1.0 / 60.0);
+
+      let (forward, _, _) = basis(&camera);
+      let mut pitch = -forward.dot(&preset.up).asin();
+      for angle in [0.7, 0.7, 0.7, -0.7, -0.7, -0.7, -0.7, -0.7] {
+        camera.rotate_pitch(angle);
+        camera.update(1.0 / 60.0);
+        pitch = (pitch + angle).clamp(-max_pitch, max_pitch);
+
+        // Pitch positivo mira hacia abajo
+        let (forward, _, up) = basis(&camera);
+        assert!((forward.dot(&preset.up) + pitch.sin()).abs() < 1e-3, "expected pitch {}, forward {:?}", pitch, forward);
+        assert!(up.dot(&preset.up) > 0.0, "up flipped: {:?}", up);
+        assert_orthonormal(&camera);
+      }
+    }
+  }
+
+  #[test]
+  fn orbit_past_the_pole_clamps_without_flipping() {
+    let mut camera = Camera::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+    camera.smoothing = 0.0;
+    let max_pitch = PI / 2.0 - 0.1;
+
+    let mut pitch: f32 = 0.0;
+    for delta_pitch in [1.0, 1.0, 1.0, -1.0, -1.0, -1.0, -1.0] {
+      camera.orbit(0.0, delta_pitch);
+      camera.update(1.0 / 60.0);
+      pitch = (pitch + delta_pitch).clamp(-max_pitch, max_pitch);
+
+      // Con pitch positivo el ojo baja y la cámara mira hacia arriba
+      let (forward, _, up) = basis(&camera);
+      assert!((forward.y - pitch.sin()).abs() < 1e-3, "expected pitch {}, forward {:?}", pitch, forward);
+      assert!(up.y > 0.0, "up flipped: {:?}", up);
+      assert!(((camera.eye - camera.center).magnitude() - 5.0).abs() < 1e-4);
+    }
+  }
+
+  // 90° de FOV con aspecto 1 desde el origen mirando hacia -z: a una
+  // distancia d se ve de -d a d en x e y, entre z = -1 y z = -100
+  fn test_frustum() -> [Vec4; 6] {
+    let projection = transform::perspective(90.0, 100.0, 100.0, 1.0, 100.0);
+    let view = transform::view(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0), Vec3::new(0.0, 1.0, 0.0));
+    frustum_planes(&(projection * view))
+  }
+
+  #[test]
+  fn frustum_planes_have_unit_normals() {
+    for plane in test_frustum() {
+      assert!((Vec3::new(plane.x, plane.y, plane.z).magnitude() - 1.0).abs() < 1e-5);
+    }
+  }
+
+  #[test]
+  fn sphere_in_frustum_inside_outside_and_straddling() {
+    let planes = test_frustum();
+    assert!(sphere_in_frustum(&planes, &Vec3::new(0.0, 0.0, -10.0), 1.0));
+
+    // Para cada plano (izquierdo, derecho, abajo, arriba, cercano, lejano),
+    // una esfera de radio 1 fuera del todo y otra que lo cruza
+    let cases = [
+      (Vec3::new(-13.0, 0.0, -10.0), Vec3::new(-10.5, 0.0, -10.0)),
+      (Vec3::new(13.0, 0.0, -10.0), Vec3::new(10.5, 0.0, -10.0)),
+      (Vec3::new(0.0, -13.0, -10.0), Vec3::new(0.0, -10.5, -10.0)),
+      (Vec3::new(0.0, 13.0, -10.0), Vec3::new(0.0, 10.5, -10.0)),
+      (Vec3::new(0.0, 0.0, 2.0), Vec3::new(0.0, 0.0, -0.5)),
+      (Vec3::new(0.0, 0.0, -102.0), Vec3::new(0.0, 0.0, -100.5)),
+    ];
+    for (outside, straddling) in cases {
+      assert!(!sphere_in_frustum(&planes, &outside, 1.0), "{:?} should be culled", outside);
+      assert!(sphere_in_frustum(&planes, &straddling, 1.0), "{:?} should be kept", straddling);
+    }
+  }
+
+  #[test]
+  fn forward_keeps_its_length_after_rotations() {
+    let mut camera = Camera::new(Vec3::new(0.0, 0.0, 1.0), Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+    camera.smoothing = 0.0;
+
+    for step in 0..50 {
+      camera.check_if_changed();
+      if step % 2 == 0 {
+        camera.rotate_yaw(0.3);
+      } else {
+        camera.rotate_pitch(if step % 4 == 1 { 0.2 } else { -0.15 });
+      }
+      assert!(camera.check_if_changed());
+      camera.update(1.0 / 60.0);
+
+      let forward = camera.center - camera.eye;
+      assert!((forward.magnitude() - 1.0).abs() < 1e-4, "|forward| = {}", forward.magnitude());
+      assert_eq!(camera.eye, Vec3::new(0.0, 0.0, 1.0));
+    }
+  }
+
+  #[test]
+  fn yaw_turns_around_the_world_up() {
+    let mut camera = Camera::new(Vec3::new(0.0, 0.0, 1.0), Vec3::new(0.0, -0.5, 0.0), Vec3::new(0.0, 1.0, 0.0));
+    camera.smoothing = 0.0;
+    let before = camera.center - camera.eye;
+
+    camera.rotate_yaw(PI / 2.0);
+    camera.update(1.0 / 60.0);
+
+    let after = camera.center - camera.eye;
+    assert!((after.y - before.y).abs() < 1e-5);
+    // Un cuarto de vuelta: la parte horizontal queda perpendicular
+    assert!((after.dot(&before) - before.y * before.y).abs() < 1e-5);
+  }
+
+  // La parte de rotación de la matriz de vista es ortonormal y el ojo queda
+  // en el origen, también tras orbitar
+  #[test]
+  fn view_matrix_has_an_orthonormal_basis() {
+    for preset in CameraPreset::defaults() {
+      let mut camera = Camera::new(preset.eye, preset.center, preset.up);
+      camera.smoothing = 0.0;
+      camera.orbit(0.4, 0.2);
+      camera.update(1.0 / 60.0);
+
+      let view = camera.view_matrix();
+      let rotation = view.fixed_view::<3, 3>(0, 0).into_owned();
+      let identity = rotation * rotation.transpose();
+      assert!((identity - nalgebra_glm::Mat3::identity()).abs().max() < 1e-4, "{:?}", identity);
+
+      let eye = view * Vec4::new(camera.eye.x, camera.eye.y, camera.eye.z, 1.0);
+      assert!(Vec3::new(eye.x, eye.y, eye.z).magnitude() < 1e-3, "{:?}", eye);
+    }
+  }
+
+  // W mueve ojo y centro hacia adelante: el ojo se acerca a lo que antes
+  // miraba y la dirección de vista no cambia
+  #[test]
+  fn moving_forward_approaches_the_point_ahead() {
+    let ahead = Vec3::new(3.0, 1.0, -2.0);
+    let mut camera = Camera::new(Vec3::new(-5.0, 4.0, 6.0), ahead, Vec3::new(0.0, 1.0, 0.0));
+    camera.smoothing = 0.0;
+    let distance = (ahead - camera.eye).magnitude();
+    let forward = (camera.center - camera.eye).normalize();
+
+    camera.move_local(Vec3::new(0.0, 0.0, -0.5));
+    camera.update(1.0 / 60.0);
+
+    assert!(((ahead - camera.eye).magnitude() - (distance - 0.5)).abs() < 1e-4);
+    assert!(((camera.center - camera.eye).normalize() - forward).magnitude() < 1e-5);
+  }
+
+  #[test]
+  fn strafing_right_moves_along_the_camera_right_axis() {
+    let mut camera = Camera::new(Vec3::new(0.0, 0.0, 10.0), Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+    camera.smoothing = 0.0;
+    // D hacia la derecha de la cámara, que mirando hacia -z es +x
+    camera.move_local(Vec3::new(2.0, 0.0, 0.0));
+    camera.update(1.0 / 60.0);
+    assert!((camera.eye - Vec3::new(2.0, 0.0, 10.0)).magnitude() < 1e-5, "{:?}", camera.eye);
+    assert!((camera.center - Vec3::new(2.0, 0.0, 0.0)).magnitude() < 1e-5, "{:?}", camera.center);
   }
 }
@@ -0,0 +1,169 @@
+use nalgebra_glm::{Vec3, Vec4, Mat4};
+use crate::vertex::Vertex;
+
+// Signed distances to the six clip-space planes (-w <= x, y, z <= w);
+// a vertex is inside a plane when its distance is >= 0
+const CLIP_PLANES: [fn(&Vec4) -> f32; 6] = [
+    |clip| clip.z + clip.w, // near
+    |clip| clip.w - clip.z, // far
+    |clip| clip.x + clip.w, // left
+    |clip| clip.w - clip.x, // right
+    |clip| clip.y + clip.w, // bottom
+    |clip| clip.w - clip.y, // top
+];
+
+// Clips a triangle against the view frustum and appends the visible part to
+// `triangles` as a fan (nothing if it's completely outside). Triangles
+// entirely inside are appended untouched without allocating; new vertices
+// created at the intersections are projected again with the viewport matrix,
+// so every screen position handed to the rasterizer stays within the viewport.
+pub fn clip_triangle(v1: &Vertex, v2: &Vertex, v3: &Vertex, viewport_matrix: &Mat4, triangles: &mut Vec<[Vertex; 3]>) {
+    let input = [v1, v2, v3];
+    let inside_all = CLIP_PLANES.iter().all(|plane| input.iter().all(|v| plane(&v.clip_position) >= 0.0));
+    if inside_all {
+        triangles.push([v1.clone(), v2.clone(), v3.clone()]);
+        return;
+    }
+
+    // Sutherland-Hodgman, one plane at a time
+    let mut polygon: Vec<Vertex> = input.iter().map(|&v| v.clone()).collect();
+    for plane in CLIP_PLANES {
+        polygon = clip_polygon(&polygon, plane, viewport_matrix);
+        if polygon.len() < 3 {
+            return;
+        }
+    }
+
+    // Fan triangulation of the clipped polygon
+    for i in 1..polygon.len() - 1 {
+        triangles.push([polygon[0].clone(), polygon[i].clone(), polygon[i + 1].clone()]);
+    }
+}
+
+// Keeps the part of a convex polygon on the inside of a single plane
+fn clip_polygon(polygon: &[Vertex], plane: fn(&Vec4) -> f32, viewport_matrix: &Mat4) -> Vec<Vertex> {
+    let distances: Vec<f32> = polygon.iter().map(|v| plane(&v.clip_position)).collect();
+    if distances.iter().all(|&d| d >= 0.0) {
+        return polygon.to_vec();
+    }
+
+    let mut output = Vec::with_capacity(polygon.len() + 1);
+    for i in 0..polygon.len() {
+        let j = (i + 1) % polygon.len();
+        let (current, next) = (&polygon[i], &polygon[j]);
+        let (d_current, d_next) = (distances[i], distances[j]);
+
+        if d_current >= 0.0 {
+            output.push(current.clone());
+        }
+        if (d_current >= 0.0) != (d_next >= 0.0) {
+            let t = d_current / (d_current - d_next);
+            let mut vertex = lerp_vertex(current, next, t);
+            project_to_screen(&mut vertex, viewport_matrix);
+            output.push(vertex);
+        }
+    }
+    output
+}
+
+// Screen position and 1/w are recomputed from clip_position afterwards
+fn lerp_vertex(a: &Vertex, b: &Vertex, t: f32) -> Vertex {
+    Vertex::barycentric(a, b, b, 1.0 - t, t, 0.0)
+}
+
+// Perspective division and viewport transform for a clip-space vertex
+fn project_to_screen(vertex: &mut Vertex, viewport_matrix: &Mat4) {
+    let clip = vertex.clip_position;
+    let ndc_position = Vec4::new(clip.x / clip.w, clip.y / clip.w, clip.z / clip.w, 1.0);
+    let screen_position = viewport_matrix * ndc_position;
+
+    vertex.transformed_position = Vec3::new(screen_position.x, screen_position.y, screen_position.z);
+    vertex.inv_w = 1.0 / clip.w;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra_glm::Vec2;
+
+    fn clip_vertex(x: f32, y: f32, z: f32, w: f32) -> Vertex {
+        let mut vertex = Vertex::new(Vec3::new(x, y, z), Vec3::new(0.0, 0.0, 1.0), Vec2::new(0.0, 0.0));
+        vertex.clip_position = Vec4::new(x, y, z, w);
+        vertex
+    }
+
+    fn clip(v1: Vertex, v2: Vertex, v3: Vertex) -> Vec<[Vertex; 3]> {
+        let mut triangles = Vec::new();
+        clip_triangle(&v1, &v2, &v3, &Mat4::identity(), &mut triangles);
+        triangles
+    }
+
+    fn assert_inside_frustum(triangles: &[[Vertex; 3]]) {
+        for vertex in triangles.iter().flatten() {
+            for plane in CLIP_PLANES {
+                assert!(plane(&vertex.clip_position) >= -1e-5, "{:?} is outside", vertex.clip_position);
+            }
+        }
+    }
+
+    #[test]
+    fn triangle_inside_is_unchanged() {
+        let triangles = clip(
+            clip_vertex(-0.5, -0.5, 0.0, 1.0),
+            clip_vertex(0.5, -0.5, 0.0, 1.0),
+            clip_vertex(0.0, 0.5, 0.0, 1.0),
+        );
+        assert_eq!(triangles.len(), 1);
+        let expected = [Vec4::new(-0.5, -0.5, 0.0, 1.0), Vec4::new(0.5, -0.5, 0.0, 1.0), Vec4::new(0.0, 0.5, 0.0, 1.0)];
+        for (vertex, expected) in triangles[0].iter().zip(expected) {
+            assert_eq!(vertex.clip_position, expected);
+        }
+    }
+
+    #[test]
+    fn triangle_outside_is_dropped() {
+        let triangles = clip(
+            clip_vertex(2.0, 0.0, 0.0, 1.0),
+            clip_vertex(3.0, 0.0, 0.0, 1.0),
+            clip_vertex(2.5, 0.5, 0.0, 1.0),
+        );
+        assert!(triangles.is_empty());
+    }
+
+    #[test]
+    fn triangle_crossing_near_plane_is_cut_on_it() {
+        // One corner behind the near plane (z < -w): the visible part is a quad
+        let triangles = clip(
+            clip_vertex(-0.5, -0.5, -2.0, 1.0),
+            clip_vertex(0.5, -0.5, 0.5, 1.0),
+            clip_vertex(0.0, 0.5, 0.5, 1.0),
+        );
+        assert_eq!(triangles.len(), 2);
+        assert_inside_frustum(&triangles);
+
+        let on_near_plane = triangles.iter().flatten()
+            .filter(|v| (v.clip_position.z + v.clip_position.w).abs() < 1e-5)
+            .count();
+        assert!(on_near_plane >= 2);
+    }
+
+    #[test]
+    fn triangle_crossing_two_planes_stays_inside_both() {
+        // Wider than the frustum: sticks out on the left and on the right
+        let triangles = clip(
+            clip_vertex(-3.0, -0.5, 0.0, 1.0),
+            clip_vertex(3.0, -0.5, 0.0, 1.0),
+            clip_vertex(0.0, 0.5, 0.0, 1.0),
+        );
+        assert!(!triangles.is_empty());
+        assert_inside_frustum(&triangles);
+
+        let vertices: Vec<&Vertex> = triangles.iter().flatten().collect();
+        assert!(vertices.iter().any(|v| (v.clip_position.x + 1.0).abs() < 1e-5));
+        assert!(vertices.iter().any(|v| (v.clip_position.x - 1.0).abs() < 1e-5));
+        // New vertices are projected again (identity viewport, w = 1)
+        for vertex in vertices {
+            assert!((vertex.transformed_position.x - vertex.clip_position.x).abs() < 1e-5);
+        }
+    }
+}
@@ -1,22 +1,40 @@
 
 use std::fmt;
+use nalgebra_glm::Vec3;
 
-#[derive(Debug, Clone, Copy)]
+// Equality compares all four channels, so equal colors always have the same
+// `to_hex_rgba` (and `to_hex`); colors that differ only in alpha share `to_hex`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Color {
   r: u8,
   g: u8,
   b: u8,
+  a: u8,
 }
 
 impl Color {
   // Constructor to initialize the color using r, g, b values as u8
   pub fn new(r: u8, g: u8, b: u8) -> Self {
-    Color { r, g, b }
+    Color { r, g, b, a: 255 }
+  }
+
+  // Constructor with an explicit alpha channel (0 = transparent, 255 = opaque)
+  pub fn new_rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
+    Color { r, g, b, a }
+  }
+
+  // Returns the same color with a different alpha
+  pub fn with_alpha(self, a: u8) -> Self {
+    Color { a, ..self }
+  }
+
+  pub fn alpha(&self) -> u8 {
+    self.a
   }
 
   // default color
   pub fn black() -> Self {
-    Color { r: 0, g: 0, b: 0 }
+    Color { r: 0, g: 0, b: 0, a: 255 }
   }
 
   // New constructor to initialize the color using r, g, b values as f32 (0.0 to 1.0)
@@ -25,15 +43,119 @@ impl Color {
       r: (r.clamp(0.0, 1.0) * 255.0) as u8,
       g: (g.clamp(0.0, 1.0) * 255.0) as u8,
       b: (b.clamp(0.0, 1.0) * 255.0) as u8,
+      a: 255,
+    }
+  }
+
+  // Constructor from a 0.0 to 1.0 RGB vector. Components are clamped, so
+  // shaders can add up lighting terms in float and convert once at the end.
+  pub fn from_vec3(v: Vec3) -> Self {
+    Color {
+      r: (v.x.clamp(0.0, 1.0) * 255.0).round() as u8,
+      g: (v.y.clamp(0.0, 1.0) * 255.0).round() as u8,
+      b: (v.z.clamp(0.0, 1.0) * 255.0).round() as u8,
+      a: 255,
+    }
+  }
+
+  // RGB as a 0.0 to 1.0 vector (alpha is dropped)
+  pub fn to_vec3(&self) -> Vec3 {
+    Vec3::new(self.r as f32, self.g as f32, self.b as f32) / 255.0
+  }
+
+  // Constructor from HSL: hue in degrees (wraps around), saturation and lightness in 0.0 to 1.0
+  pub fn from_hsl(h: f32, s: f32, l: f32) -> Self {
+    let h = h.rem_euclid(360.0);
+    let s = s.clamp(0.0, 1.0);
+    let l = l.clamp(0.0, 1.0);
+
+    let chroma = (1.0 - (2.0 * l - 1.0).abs()) * s;
+    let x = chroma * (1.0 - ((h / 60.0) % 2.0 - 1.0).abs());
+    let m = l - chroma / 2.0;
+
+    let (r, g, b) = match (h / 60.0) as u32 {
+      0 => (chroma, x, 0.0),
+      1 => (x, chroma, 0.0),
+      2 => (0.0, chroma, x),
+      3 => (0.0, x, chroma),
+      4 => (x, 0.0, chroma),
+      _ => (chroma, 0.0, x),
+    };
+
+    Color {
+      r: ((r + m) * 255.0).round().clamp(0.0, 255.0) as u8,
+      g: ((g + m) * 255.0).round().clamp(0.0, 255.0) as u8,
+      b: ((b + m) * 255.0).round().clamp(0.0, 255.0) as u8,
+      a: 255,
+    }
+  }
+
+  // Returns (hue in degrees, saturation, lightness)
+  pub fn to_hsl(&self) -> (f32, f32, f32) {
+    let r = self.r as f32 / 255.0;
+    let g = self.g as f32 / 255.0;
+    let b = self.b as f32 / 255.0;
+
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let l = (max + min) / 2.0;
+    let delta = max - min;
+
+    if delta == 0.0 {
+      return (0.0, 0.0, l);
+    }
+
+    let s = delta / (1.0 - (2.0 * l - 1.0).abs());
+    let h = if max == r {
+      60.0 * ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+      60.0 * ((b - r) / delta + 2.0)
+    } else {
+      60.0 * ((r - g) / delta + 4.0)
+    };
+
+    (h, s, l)
+  }
+
+  // Constructor from HSV: hue in degrees (wraps around), saturation and value in 0.0 to 1.0
+  pub fn from_hsv(h: f32, s: f32, v: f32) -> Self {
+    let h = h.rem_euclid(360.0);
+    let s = s.clamp(0.0, 1.0);
+    let v = v.clamp(0.0, 1.0);
+
+    let chroma = v * s;
+    let x = chroma * (1.0 - ((h / 60.0) % 2.0 - 1.0).abs());
+    let m = v - chroma;
+
+    let (r, g, b) = match (h / 60.0) as u32 {
+      0 => (chroma, x, 0.0),
+      1 => (x, chroma, 0.0),
+      2 => (0.0, chroma, x),
+      3 => (0.0, x, chroma),
+      4 => (x, 0.0, chroma),
+      _ => (chroma, 0.0, x),
+    };
+
+    Color {
+      r: ((r + m) * 255.0).round().clamp(0.0, 255.0) as u8,
+      g: ((g + m) * 255.0).round().clamp(0.0, 255.0) as u8,
+      b: ((b + m) * 255.0).round().clamp(0.0, 255.0) as u8,
+      a: 255,
     }
   }
 
+  // Lightens (positive delta) or darkens (negative delta) keeping hue and saturation
+  pub fn adjust_lightness(&self, delta: f32) -> Color {
+    let (h, s, l) = self.to_hsl();
+    Color { a: self.a, ..Color::from_hsl(h, s, l + delta) }
+  }
+
   // Function to create a color from a hex value
   pub fn from_hex(hex: u32) -> Self {
     let r = ((hex >> 16) & 0xFF) as u8;
     let g = ((hex >> 8) & 0xFF) as u8;
     let b = (hex & 0xFF) as u8;
-    Color { r, g, b }
+    Color { r, g, b, a: 255 }
   }
 
   // Function to return the color as a hex value
@@ -41,6 +163,17 @@ impl Color {
     ((self.r as u32) << 16) | ((self.g as u32) << 8) | (self.b as u32)
   }
 
+  // Function to create a color from a 0xAARRGGBB hex value
+  pub fn from_hex_rgba(hex: u32) -> Self {
+    let a = ((hex >> 24) & 0xFF) as u8;
+    Color { a, ..Color::from_hex(hex) }
+  }
+
+  // Function to return the color as a 0xAARRGGBB hex value
+  pub fn to_hex_rgba(&self) -> u32 {
+    ((self.a as u32) << 24) | self.to_hex()
+  }
+
   // Linear interpolation between two colors
   pub fn lerp(&self, other: &Color, t: f32) -> Self {
     let t = t.clamp(0.0, 1.0);
@@ -48,9 +181,40 @@ impl Color {
       r: (self.r as f32 + (other.r as f32 - self.r as f32) * t).round() as u8,
       g: (self.g as f32 + (other.g as f32 - self.g as f32) * t).round() as u8,
       b: (self.b as f32 + (other.b as f32 - self.b as f32) * t).round() as u8,
+      a: (self.a as f32 + (other.a as f32 - self.a as f32) * t).round() as u8,
     }
   }
 
+  // Relative luminance with Rec. 709 weights, 0.0 (black) to 1.0 (white)
+  pub fn luminance(&self) -> f32 {
+    (0.2126 * self.r as f32 + 0.7152 * self.g as f32 + 0.0722 * self.b as f32) / 255.0
+  }
+
+  // Gray with the same luminance in all three channels
+  pub fn grayscale(&self) -> Color {
+    let l = (self.luminance() * 255.0).round() as u8;
+    Color { r: l, g: l, b: l, a: self.a }
+  }
+
+  // Classic sepia-tone matrix, clamped to the 0-255 range
+  pub fn sepia(&self) -> Color {
+    let (r, g, b) = (self.r as f32, self.g as f32, self.b as f32);
+    Color {
+      r: (0.393 * r + 0.769 * g + 0.189 * b).round().min(255.0) as u8,
+      g: (0.349 * r + 0.686 * g + 0.168 * b).round().min(255.0) as u8,
+      b: (0.272 * r + 0.534 * g + 0.131 * b).round().min(255.0) as u8,
+      a: self.a,
+    }
+  }
+
+  pub fn is_bright(&self, threshold: f32) -> bool {
+    self.luminance() > threshold
+  }
+
+  pub fn is_opaque(&self) -> bool {
+    self.a == 255
+  }
+
   pub fn is_black(&self) -> bool {
     self.r == 0 && self.g == 0 && self.b == 0 
   }
@@ -60,6 +224,28 @@ impl Color {
     if blend.is_black() { *self } else { *blend }
   }
 
+  // Source-over compositing of `over` on top of `self` using their alpha
+  pub fn blend_alpha(&self, over: &Color) -> Color {
+    let src_a = over.a as f32 / 255.0;
+    let dst_a = self.a as f32 / 255.0;
+    let out_a = src_a + dst_a * (1.0 - src_a);
+
+    if out_a <= 0.0 {
+      return Color::new_rgba(0, 0, 0, 0);
+    }
+
+    let channel = |src: u8, dst: u8| {
+      ((src as f32 * src_a + dst as f32 * dst_a * (1.0 - src_a)) / out_a).round().clamp(0.0, 255.0) as u8
+    };
+
+    Color {
+      r: channel(over.r, self.r),
+      g: channel(over.g, self.g),
+      b: channel(over.b, self.b),
+      a: (out_a * 255.0).round() as u8,
+    }
+  }
+
   pub fn blend_multiply(&self, blend: &Color) -> Color {
     Color::new(
       ((self.r as f32 * blend.r as f32) / 255.0) as u8,
@@ -92,6 +278,41 @@ impl Color {
     )
   }
 
+  // Multiply where the base is dark, screen where it is light
+  pub fn blend_overlay(&self, blend: &Color) -> Color {
+    let channel = |base: u8, top: u8| {
+      let (base, top) = (base as u16, top as u16);
+      if base < 128 {
+        (2 * base * top / 255) as u8
+      } else {
+        (255 - 2 * (255 - base) * (255 - top) / 255) as u8
+      }
+    };
+    Color::new(channel(self.r, blend.r), channel(self.g, blend.g), channel(self.b, blend.b))
+  }
+
+  // Photoshop soft light: a gentler overlay that never reaches pure black or white
+  pub fn blend_soft_light(&self, blend: &Color) -> Color {
+    let channel = |base: u8, top: u8| {
+      let (base, top) = (base as f32 / 255.0, top as f32 / 255.0);
+      let result = if top <= 0.5 {
+        base - (1.0 - 2.0 * top) * base * (1.0 - base)
+      } else {
+        base + (2.0 * top - 1.0) * (base.sqrt() - base)
+      };
+      (result * 255.0).round().clamp(0.0, 255.0) as u8
+    };
+    Color::new(channel(self.r, blend.r), channel(self.g, blend.g), channel(self.b, blend.b))
+  }
+
+  pub fn blend_darken(&self, blend: &Color) -> Color {
+    Color::new(self.r.min(blend.r), self.g.min(blend.g), self.b.min(blend.b))
+  }
+
+  pub fn blend_lighten(&self, blend: &Color) -> Color {
+    Color::new(self.r.max(blend.r), self.g.max(blend.g), self.b.max(blend.b))
+  }
+
 }
 
 // Implement addition for Color
@@ -105,6 +326,23 @@ impl Add for Color {
       r: self.r.saturating_add(other.r),
       g: self.g.saturating_add(other.g),
       b: self.b.saturating_add(other.b),
+      a: self.a,
+    }
+  }
+}
+
+// Implement subtraction for Color
+use std::ops::Sub;
+
+impl Sub for Color {
+  type Output = Color;
+
+  fn sub(self, other: Color) -> Color {
+    Color {
+      r: self.r.saturating_sub(other.r),
+      g: self.g.saturating_sub(other.g),
+      b: self.b.saturating_sub(other.b),
+      a: self.a,
     }
   }
 }
@@ -120,6 +358,23 @@ impl Mul<f32> for Color {
       r: (self.r as f32 * scalar).clamp(0.0, 255.0) as u8,
       g: (self.g as f32 * scalar).clamp(0.0, 255.0) as u8,
       b: (self.b as f32 * scalar).clamp(0.0, 255.0) as u8,
+      a: self.a,
+    }
+  }
+}
+
+// Implement division by a constant for Color
+use std::ops::Div;
+
+impl Div<f32> for Color {
+  type Output = Color;
+
+  fn div(self, scalar: f32) -> Color {
+    Color {
+      r: (self.r as f32 / scalar).clamp(0.0, 255.0) as u8,
+      g: (self.g as f32 / scalar).clamp(0.0, 255.0) as u8,
+      b: (self.b as f32 / scalar).clamp(0.0, 255.0) as u8,
+      a: self.a,
     }
   }
 }
@@ -127,6 +382,202 @@ impl Mul<f32> for Color {
 // Implement display formatting for Color
 impl fmt::Display for Color {
   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-    write!(f, "Color(r: {}, g: {}, b: {})", self.r, self.g, self.b)
+    write!(f, "Color(r: {}, g: {}, b: {}, a: {})", self.r, self.g, self.b, self.a)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn blend_modes_match_hand_computed_channels() {
+    let base = Color::new(200, 100, 50);
+    let top = Color::new(100, 200, 255);
+
+    assert_eq!(base.blend_multiply(&top), Color::new(78, 78, 50));
+    assert_eq!(base.blend_screen(&top), Color::new(222, 222, 255));
+    assert_eq!(base.blend_darken(&top), Color::new(100, 100, 50));
+    assert_eq!(base.blend_lighten(&top), Color::new(200, 200, 255));
+    // Overlay: screen on the light red channel, multiply on the darker ones
+    assert_eq!(base.blend_overlay(&top), Color::new(189, 156, 100));
+    assert_eq!(base.blend_soft_light(&top), Color::new(191, 134, 113));
+    // A black layer keeps the base in normal mode
+    assert_eq!(base.blend_normal(&Color::black()), base);
+    assert_eq!(base.blend_normal(&top), top);
+  }
+
+  #[test]
+  fn additive_blends_saturate() {
+    let light = Color::new(200, 100, 50);
+    let dark = Color::new(100, 200, 255);
+
+    assert_eq!(light.blend_add(&dark), Color::new(255, 255, 255));
+    assert_eq!(light.blend_subtract(&dark), Color::new(100, 0, 0));
+    assert_eq!(light + dark, Color::new(255, 255, 255));
+    assert_eq!(light - dark, Color::new(100, 0, 0));
+    assert_eq!(Color::black() - light, Color::black());
+  }
+
+  #[test]
+  fn scalar_operators_clamp() {
+    let color = Color::new(200, 100, 50);
+
+    assert_eq!(color / 2.0, Color::new(100, 50, 25));
+    assert_eq!(color / 0.5, Color::new(255, 200, 100));
+    assert_eq!(color / -1.0, Color::black());
+    assert_eq!(color * 2.0, Color::new(255, 200, 100));
+    assert_eq!(color * -1.0, Color::black());
+    // Dividing and multiplying back only loses the truncated half
+    assert_eq!((color / 2.0) * 2.0, color);
+  }
+
+  #[test]
+  fn hsl_round_trips() {
+    for r in (0..=255).step_by(15) {
+      for g in (0..=255).step_by(15) {
+        for b in (0..=255).step_by(15) {
+          let color = Color::new(r, g, b);
+          let (h, s, l) = color.to_hsl();
+          assert_eq!(Color::from_hsl(h, s, l), color, "hsl {} {} {}", h, s, l);
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn hsl_and_hsv_wrap_hue_and_clamp_the_rest() {
+    assert_eq!(Color::from_hsl(480.0, 1.0, 0.5), Color::from_hsl(120.0, 1.0, 0.5));
+    assert_eq!(Color::from_hsl(-120.0, 1.0, 0.5), Color::from_hsl(240.0, 1.0, 0.5));
+    assert_eq!(Color::from_hsl(0.0, 3.0, 0.5), Color::new(255, 0, 0));
+    assert_eq!(Color::from_hsl(0.0, 1.0, 1.5), Color::new(255, 255, 255));
+    assert_eq!(Color::from_hsl(0.0, 1.0, -0.5), Color::black());
+
+    assert_eq!(Color::from_hsv(120.0, 1.0, 1.0), Color::new(0, 255, 0));
+    assert_eq!(Color::from_hsv(600.0, 1.0, 0.5), Color::new(0, 0, 128));
+    assert_eq!(Color::from_hsv(0.0, -1.0, 2.0), Color::new(255, 255, 255));
+  }
+
+  // A fully saturated color at full value is the same as at half lightness
+  #[test]
+  fn hsv_agrees_with_hsl() {
+    for h in (0..360).step_by(5) {
+      let h = h as f32;
+      assert_eq!(Color::from_hsv(h, 1.0, 1.0), Color::from_hsl(h, 1.0, 0.5), "hue {}", h);
+      let (hsl_h, _, _) = Color::from_hsv(h, 1.0, 1.0).to_hsl();
+      assert!((hsl_h - h).abs() < 1.0 || (hsl_h - h).abs() > 359.0, "hue {} came back as {}", h, hsl_h);
+    }
+  }
+
+  #[test]
+  fn adjust_lightness_keeps_hue_and_saturates() {
+    let color = Color::new_rgba(40, 120, 200, 90);
+    let (h, s, _) = color.to_hsl();
+    let (lighter_h, lighter_s, _) = color.adjust_lightness(0.2).to_hsl();
+    assert!((lighter_h - h).abs() < 1.0 && (lighter_s - s).abs() < 0.02);
+
+    assert_eq!(color.adjust_lightness(2.0), Color::new_rgba(255, 255, 255, 90));
+    assert_eq!(color.adjust_lightness(-2.0), Color::new_rgba(0, 0, 0, 90));
+  }
+
+  #[test]
+  fn luminance_follows_rec709_weights() {
+    assert_eq!(Color::black().luminance(), 0.0);
+    assert!((Color::new(255, 255, 255).luminance() - 1.0).abs() < 1e-6);
+
+    let (red, green, blue) = (Color::new(255, 0, 0), Color::new(0, 255, 0), Color::new(0, 0, 255));
+    assert!(green.luminance() > red.luminance());
+    assert!(red.luminance() > blue.luminance());
+    assert!((red.luminance() + green.luminance() + blue.luminance() - 1.0).abs() < 1e-6);
+
+    assert!(green.is_bright(0.5));
+    assert!(!blue.is_bright(0.5));
+  }
+
+  #[test]
+  fn grays_survive_grayscale_and_sepia_saturates() {
+    for value in 0..=255 {
+      let gray = Color::new(value, value, value);
+      assert_eq!(gray.grayscale(), gray);
+    }
+
+    // The sepia rows add up to more than 1, so white clips
+    assert_eq!(Color::new(255, 255, 255).sepia(), Color::new(255, 255, 239));
+    assert_eq!(Color::black().sepia(), Color::black());
+    assert_eq!(Color::new_rgba(10, 20, 30, 7).sepia().alpha(), 7);
+  }
+
+  #[test]
+  fn alpha_round_trips_through_rgba_hex() {
+    let color = Color::new_rgba(12, 34, 56, 78);
+    assert_eq!(color.to_hex_rgba(), 0x4E0C2238);
+    assert_eq!(Color::from_hex_rgba(color.to_hex_rgba()), color);
+    // Plain hex drops alpha and reads back opaque
+    assert_eq!(Color::from_hex(color.to_hex()), color.with_alpha(255));
+    assert!(!color.is_opaque() && color.with_alpha(255).is_opaque());
+    // Arithmetic keeps the left-hand alpha
+    assert_eq!((color + Color::new(1, 1, 1)).alpha(), 78);
+    assert_eq!((color * 2.0).alpha(), 78);
+  }
+
+  #[test]
+  fn blend_alpha_composites_source_over() {
+    let background = Color::new(0, 0, 200);
+    assert_eq!(background.blend_alpha(&Color::new(255, 0, 0)), Color::new(255, 0, 0));
+    assert_eq!(background.blend_alpha(&Color::new_rgba(255, 0, 0, 0)), background);
+    assert_eq!(background.blend_alpha(&Color::new_rgba(255, 0, 0, 128)), Color::new(128, 0, 100));
+    // Two transparent layers stay transparent instead of dividing by zero
+    let clear = Color::new_rgba(0, 0, 0, 0);
+    assert_eq!(clear.blend_alpha(&clear), clear);
+  }
+
+  #[test]
+  fn from_vec3_clamps_out_of_range_components() {
+    assert_eq!(Color::from_vec3(Vec3::new(1.5, -0.5, 0.5)), Color::new(255, 0, 128));
+    assert_eq!(Color::from_vec3(Vec3::new(100.0, 100.0, 100.0)), Color::new(255, 255, 255));
+    assert_eq!(Color::new_rgba(255, 0, 0, 10).to_vec3(), Vec3::new(1.0, 0.0, 0.0));
+  }
+
+  #[test]
+  fn grayscale_of_a_pure_color_is_its_luminance() {
+    for color in [Color::new(255, 0, 0), Color::new(0, 255, 0), Color::new(0, 0, 255), Color::new(255, 255, 0)] {
+      let expected = (color.luminance() * 255.0).round() as u8;
+      assert_eq!(color.grayscale(), Color::new(expected, expected, expected), "{}", color);
+    }
+    assert_eq!(Color::new_rgba(255, 0, 0, 40).grayscale().alpha(), 40);
+  }
+
+  #[test]
+  fn sepia_tints_gray_toward_brown() {
+    let toned = Color::new(100, 100, 100).sepia();
+    assert_eq!(toned, Color::new(135, 120, 94));
+    // Each row of the matrix outweighs the next, so any input comes out
+    // with red >= green >= blue
+    for r in (0..=255).step_by(51) {
+      for g in (0..=255).step_by(51) {
+        for b in (0..=255).step_by(51) {
+          let toned = Color::new(r, g, b).sepia().to_vec3();
+          assert!(toned.x >= toned.y && toned.y >= toned.z, "{:?}", toned);
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn vec3_round_trips_every_channel_value() {
+    for value in 0..=255 {
+      let color = Color::new(value, 255 - value, value / 2);
+      assert_eq!(Color::from_vec3(color.to_vec3()), color);
+    }
+  }
+
+  // Lighting terms can add up past 1 in float; converting once at the end
+  // saturates like adding the u8 colors would
+  #[test]
+  fn float_math_saturates_on_conversion() {
+    let (a, b) = (Color::new(200, 100, 50), Color::new(100, 200, 20));
+    assert_eq!(Color::from_vec3(a.to_vec3() + b.to_vec3()), a + b);
+    assert_eq!(Color::from_vec3(a.to_vec3() - b.to_vec3()), a - b);
+    assert_eq!(Color::from_vec3(a.to_vec3() * 4.0), a * 4.0);
   }
 }
@@ -1,33 +1,52 @@
 use nalgebra_glm::{Vec3, Vec2};
 use crate::color::Color;
 
+// Todo lo que un fragment shader puede leer de un píxel cubierto por un
+// triángulo. `triangle()` rellena cada campo: la posición y la profundidad
+// se interpolan de forma lineal en pantalla (la z en NDC ya está dividida por
+// w) y el resto de atributos con corrección de perspectiva.
 pub struct Fragment {
-    pub position: Vec2,
-    pub color: Color,
+    // Posición en pantalla: x e y son el píxel y z la profundidad
+    pub position: Vec3,
+    // Profundidad en NDC (igual a position.z), la que usa el z-buffer
     pub depth: f32,
+    // Normal interpolada en espacio del mundo (ya aplicada la matriz del modelo)
     pub normal: Vec3,
+    // Posición interpolada en espacio del objeto (antes de la matriz del modelo);
+    // los shaders muestrean el ruido aquí para que la textura gire con el planeta
+    pub object_position: Vec3,
+    // Coordenadas de textura del modelo
+    pub tex_coords: Vec2,
+    // Color de los vértices interpolado (con Gouraud, el color ya sombreado)
+    pub color: Color,
+    // Iluminación difusa de una luz fija hacia +z; el fragment shader la
+    // reemplaza con la de las luces de la escena
     pub intensity: f32,
-    pub vertex_position: Vec3,
+    // Tangente en espacio del mundo (dirección +U), para el normal mapping
+    pub tangent: Vec3,
+    // Fracción del píxel cubierta por el triángulo (1.0 sin antialiasing por cobertura)
+    pub coverage: f32,
 }
 
 impl Fragment {
     pub fn new(
-        position: Vec2,
-        color: Color,
-        depth: f32,
+        position: Vec3,
         normal: Vec3,
+        object_position: Vec3,
+        tex_coords: Vec2,
+        color: Color,
         intensity: f32,
-        vertex_position: Vec3,
-    ) -> Self {  
+    ) -> Self {
         Fragment {
             position,
-            color,
-            depth,
+            depth: position.z,
             normal,
+            object_position,
+            tex_coords,
+            color,
             intensity,
-            vertex_position,
+            tangent: Vec3::new(1.0, 0.0, 0.0),
+            coverage: 1.0,
         }
     }
 }
-
-
@@ -1,10 +1,29 @@
 // framebuffer.rs
 
+use std::fs;
+use std::io;
+use std::ops::Range;
+use image::RgbImage;
+use rayon::prelude::*;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use crate::color::Color;
+use crate::font::{self, GLYPH_WIDTH, GLYPH_HEIGHT};
+
+// Ordered-dither thresholds (0-15), each value appears once
+const BAYER_4X4: [[u8; 4]; 4] = [
+    [0, 8, 2, 10],
+    [12, 4, 14, 6],
+    [3, 11, 1, 9],
+    [15, 7, 13, 5],
+];
+
 pub struct Framebuffer {
     pub width: usize,
     pub height: usize,
     pub buffer: Vec<u32>,
     pub zbuffer: Vec<f32>,
+    pub supersample_factor: usize,
     background_color: u32,
     current_color: u32,
 }
@@ -16,11 +35,78 @@ impl Framebuffer {
             height,
             buffer: vec![0; width * height],
             zbuffer: vec![f32::INFINITY; width * height],
+            supersample_factor: 1,
             background_color: 0x000000,
             current_color: 0xFFFFFF,
         }
     }
 
+    // Renders internally at `factor` times the output resolution in each axis.
+    // Shading cost grows with factor², so 2× already means 4× the fragments.
+    pub fn new_supersampled(width: usize, height: usize, factor: usize) -> Self {
+        let factor = factor.max(1);
+        let mut framebuffer = Framebuffer::new(width * factor, height * factor);
+        framebuffer.supersample_factor = factor;
+        framebuffer
+    }
+
+    pub fn output_width(&self) -> usize {
+        self.width / self.supersample_factor
+    }
+
+    pub fn output_height(&self) -> usize {
+        self.height / self.supersample_factor
+    }
+
+    // Box-filter resolve of the supersampled buffer to the output resolution
+    pub fn downsample(&self) -> Vec<u32> {
+        let factor = self.supersample_factor;
+        if factor == 1 {
+            return self.buffer.clone();
+        }
+
+        let (out_width, out_height) = (self.output_width(), self.output_height());
+        let samples = (factor * factor) as u32;
+        let mut output = Vec::with_capacity(out_width * out_height);
+
+        for y in 0..out_height {
+            for x in 0..out_width {
+                let (mut r, mut g, mut b) = (0u32, 0u32, 0u32);
+                for sy in 0..factor {
+                    let row = (y * factor + sy) * self.width;
+                    for sx in 0..factor {
+                        let pixel = self.buffer[row + x * factor + sx];
+                        r += (pixel >> 16) & 0xFF;
+                        g += (pixel >> 8) & 0xFF;
+                        b += pixel & 0xFF;
+                    }
+                }
+                output.push(((r / samples) << 16) | ((g / samples) << 8) | (b / samples));
+            }
+        }
+
+        output
+    }
+
+    // Resolves the supersampled buffer and stretches it (nearest neighbour) to
+    // the window size, for when the scene renders below window resolution
+    pub fn present(&self, width: usize, height: usize) -> Vec<u32> {
+        let resolved = self.downsample();
+        let (source_width, source_height) = (self.output_width(), self.output_height());
+        if (source_width, source_height) == (width, height) {
+            return resolved;
+        }
+
+        let mut output = Vec::with_capacity(width * height);
+        for y in 0..height {
+            let row = (y * source_height / height) * source_width;
+            for x in 0..width {
+                output.push(resolved[row + x * source_width / width]);
+            }
+        }
+        output
+    }
+
     pub fn clear(&mut self) {
         for pixel in self.buffer.iter_mut() {
             *pixel = self.background_color;
@@ -30,6 +116,30 @@ impl Framebuffer {
         }
     }
 
+    // Like `clear`, but fills the color buffer with a vertical gradient from
+    // `top` (first row) to `bottom` (last row)
+    pub fn clear_gradient(&mut self, top: Color, bottom: Color) {
+        let last_row = self.height.saturating_sub(1).max(1) as f32;
+        for (y, row) in self.buffer.chunks_mut(self.width).enumerate() {
+            let color = top.lerp(&bottom, y as f32 / last_row).to_hex();
+            row.fill(color);
+        }
+        self.zbuffer.fill(f32::INFINITY);
+    }
+
+    // Scatters stars in screen space; the same seed always gives the same sky
+    pub fn draw_starfield(&mut self, seed: u64, density: f32) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let star_count = (self.width * self.height) as f32 * density.clamp(0.0, 1.0);
+
+        for _ in 0..star_count as usize {
+            let x = rng.gen_range(0..self.width);
+            let y = rng.gen_range(0..self.height);
+            let brightness: u32 = rng.gen_range(80..=255);
+            self.buffer[y * self.width + x] = (brightness << 16) | (brightness << 8) | brightness;
+        }
+    }
+
     pub fn point(&mut self, x: usize, y: usize, depth: f32) {
         if x < self.width && y < self.height {
             let index = y * self.width + x;
@@ -40,6 +150,183 @@ impl Framebuffer {
         }
     }
 
+    // Bresenham line in the current color, drawn on top of everything (no
+    // depth test) so it works for debug overlays. Pixels outside the
+    // framebuffer are skipped, so endpoints may lie off-screen.
+    pub fn line(&mut self, x0: i32, y0: i32, x1: i32, y1: i32) {
+        self.trace_line(x0, y0, x1, y1, |framebuffer, index, _| {
+            framebuffer.buffer[index] = framebuffer.current_color;
+        });
+    }
+
+    // Same as `line`, but depth is interpolated linearly between the
+    // endpoints and each pixel is depth-tested and written like `point`, so
+    // the line hides behind geometry and geometry drawn later hides it.
+    pub fn line_with_depth(&mut self, (x0, y0, z0): (i32, i32, f32), (x1, y1, z1): (i32, i32, f32)) {
+        self.trace_line(x0, y0, x1, y1, |framebuffer, index, t| {
+            let depth = z0 + (z1 - z0) * t;
+            if framebuffer.zbuffer[index] > depth {
+                framebuffer.buffer[index] = framebuffer.current_color;
+                framebuffer.zbuffer[index] = depth;
+            }
+        });
+    }
+
+    // Walks the line with Bresenham and calls `plot` with the buffer index of
+    // every on-screen pixel and how far along the line it is (0 to 1)
+    fn trace_line(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, mut plot: impl FnMut(&mut Self, usize, f32)) {
+        let (width, height) = (self.width as i32, self.height as i32);
+        // Si ambos extremos quedan del mismo lado fuera de la pantalla no hay nada que dibujar
+        if (x0 < 0 && x1 < 0) || (y0 < 0 && y1 < 0) || (x0 >= width && x1 >= width) || (y0 >= height && y1 >= height) {
+            return;
+        }
+
+        let dx = (x1 - x0).abs();
+        let dy = -(y1 - y0).abs();
+        let step_x = if x0 < x1 { 1 } else { -1 };
+        let step_y = if y0 < y1 { 1 } else { -1 };
+        let total_steps = dx.max(-dy).max(1) as f32;
+        let mut error = dx + dy;
+        let (mut x, mut y) = (x0, y0);
+        let mut steps = 0;
+
+        loop {
+            if x >= 0 && x < width && y >= 0 && y < height {
+                let t = (steps as f32 / total_steps).min(1.0);
+                plot(self, y as usize * self.width + x as usize, t);
+            }
+            if x == x1 && y == y1 {
+                break;
+            }
+            let doubled_error = 2 * error;
+            if doubled_error >= dy {
+                error += dy;
+                x += step_x;
+            }
+            if doubled_error <= dx {
+                error += dx;
+                y += step_y;
+            }
+            steps += 1;
+        }
+    }
+
+    // Depth-tested alpha blend that leaves the depth buffer untouched, so
+    // transparent surfaces don't hide whatever is drawn behind them later
+    pub fn blend_point(&mut self, x: usize, y: usize, depth: f32, color: &Color) {
+        if x < self.width && y < self.height {
+            let index = y * self.width + x;
+            if self.zbuffer[index] > depth {
+                let background = Color::from_hex(self.buffer[index]);
+                self.buffer[index] = background.blend_alpha(color).to_hex();
+            }
+        }
+    }
+
+    // Draws `text` with the 5×7 bitmap font, top-left corner at (x, y) in
+    // framebuffer pixels. Each font pixel covers supersample_factor² pixels
+    // so text keeps its size on screen. There's no depth test; pixels off the
+    // framebuffer are clipped, characters without a glyph are left blank and
+    // '\n' starts a new line.
+    pub fn draw_text(&mut self, x: i32, y: i32, text: &str, color: Color) {
+        let scale = self.supersample_factor as i32;
+        let advance = (GLYPH_WIDTH as i32 + 1) * scale;
+        let line_height = (GLYPH_HEIGHT as i32 + 2) * scale;
+        let hex = color.to_hex();
+        let (mut cursor_x, mut cursor_y) = (x, y);
+
+        for c in text.chars() {
+            if c == '\n' {
+                cursor_x = x;
+                cursor_y += line_height;
+                continue;
+            }
+            if let Some(rows) = font::glyph(c) {
+                for (row, bits) in rows.iter().enumerate() {
+                    for column in 0..GLYPH_WIDTH {
+                        if bits & (1 << (GLYPH_WIDTH - 1 - column)) != 0 {
+                            let px = cursor_x + column as i32 * scale;
+                            let py = cursor_y + row as i32 * scale;
+                            self.fill_rect(px, py, scale, scale, hex);
+                        }
+                    }
+                }
+            }
+            cursor_x += advance;
+        }
+    }
+
+    // Solid rectangle clipped to the framebuffer, without depth test
+    fn fill_rect(&mut self, x: i32, y: i32, width: i32, height: i32, color: u32) {
+        let x0 = x.clamp(0, self.width as i32) as usize;
+        let y0 = y.clamp(0, self.height as i32) as usize;
+        let x1 = (x + width).clamp(0, self.width as i32) as usize;
+        let y1 = (y + height).clamp(0, self.height as i32) as usize;
+        for row in y0..y1 {
+            self.buffer[row * self.width + x0..row * self.width + x1].fill(color);
+        }
+    }
+
+    pub fn get_pixel(&self, x: usize, y: usize) -> Option<u32> {
+        if x < self.width && y < self.height {
+            Some(self.buffer[y * self.width + x])
+        } else {
+            None
+        }
+    }
+
+    pub fn depth_at(&self, x: usize, y: usize) -> f32 {
+        if x < self.width && y < self.height {
+            self.zbuffer[y * self.width + x]
+        } else {
+            f32::INFINITY
+        }
+    }
+
+    // Whether a fragment at `depth` would pass the depth test right now.
+    // Lets the pipeline skip shading occluded fragments; the write itself
+    // still tests again, since later fragments may be closer.
+    pub fn depth_test(&self, x: usize, y: usize, depth: f32) -> bool {
+        self.depth_at(x, y) > depth
+    }
+
+    pub fn depth_buffer(&self) -> &[f32] {
+        &self.zbuffer
+    }
+
+    // Splits the color and depth buffers into bands of `rows` full-width rows
+    // (the last one may be shorter). Bands are disjoint slices of the
+    // buffers, so each one can be drawn on its own thread and there is
+    // nothing to copy back afterwards.
+    pub fn tiles_mut(&mut self, rows: usize) -> impl IndexedParallelIterator<Item = FramebufferTile<'_>> {
+        let width = self.width;
+        let rows = rows.max(1);
+        self.buffer
+            .par_chunks_mut(width * rows)
+            .zip(self.zbuffer.par_chunks_mut(width * rows))
+            .enumerate()
+            .map(move |(index, (buffer, zbuffer))| FramebufferTile {
+                width,
+                first_row: index * rows,
+                buffer,
+                zbuffer,
+            })
+    }
+
+    // Replaces the color buffer with the depth buffer in grayscale: `near`
+    // maps to white and `far` to black. Pixels nothing was drawn on stay black.
+    pub fn visualize_depth(&mut self, near: f32, far: f32) {
+        let range = (far - near).max(f32::EPSILON);
+        for (pixel, depth) in self.buffer.iter_mut().zip(self.zbuffer.iter()) {
+            if depth.is_infinite() {
+                *pixel = 0x000000;
+                continue;
+            }
+            let brightness = ((1.0 - (depth - near) / range).clamp(0.0, 1.0) * 255.0) as u32;
+            *pixel = (brightness << 16) | (brightness << 8) | brightness;
+        }
+    }
+
     pub fn set_background_color(&mut self, color: u32) {
         self.background_color = color;
     }
@@ -47,4 +334,544 @@ impl Framebuffer {
     pub fn set_current_color(&mut self, color: u32) {
         self.current_color = color;
     }
+
+    // Darkens pixels by their normalized distance from the center (1.0 at the
+    // corners). Strength 0 leaves the image untouched.
+    pub fn apply_vignette(&mut self, strength: f32) {
+        if strength <= 0.0 {
+            return;
+        }
+
+        let center_x = (self.width as f32 - 1.0) / 2.0;
+        let center_y = (self.height as f32 - 1.0) / 2.0;
+        let max_distance = (center_x * center_x + center_y * center_y).sqrt().max(1.0);
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let dx = x as f32 - center_x;
+                let dy = y as f32 - center_y;
+                let distance = (dx * dx + dy * dy).sqrt() / max_distance;
+                let factor = (1.0 - strength * distance * distance).clamp(0.0, 1.0);
+                if factor >= 1.0 {
+                    continue;
+                }
+
+                let index = y * self.width + x;
+                let pixel = self.buffer[index];
+                let r = (((pixel >> 16) & 0xFF) as f32 * factor).round() as u32;
+                let g = (((pixel >> 8) & 0xFF) as f32 * factor).round() as u32;
+                let b = ((pixel & 0xFF) as f32 * factor).round() as u32;
+                self.buffer[index] = (r << 16) | (g << 8) | b;
+            }
+        }
+    }
+
+    // Replaces every pixel with `filter(pixel)`, e.g. `Color::grayscale`
+    pub fn map_colors(&mut self, filter: fn(&Color) -> Color) {
+        for pixel in self.buffer.iter_mut() {
+            *pixel = filter(&Color::from_hex(*pixel)).to_hex();
+        }
+    }
+
+    // This is the tone-map pass: `exposure` multiplies the linear channels
+    // before the gamma curve and whatever goes past 1.0 clips to white, since
+    // the buffer is 8-bit. An exposure of 1.0 leaves the input unchanged.
+    // With `dither`, a 4×4 Bayer offset in (-0.5, 0.5) is added before
+    // rounding each channel back to 8 bits, which breaks smooth gradients'
+    // bands into a fine pattern. The offsets average to zero, so the mean
+    // color is kept. The pattern is indexed by output pixel, so each
+    // supersampled block shares one offset and downsampling doesn't average it away.
+    pub fn apply_gamma(&mut self, gamma: f32, exposure: f32, dither: bool) {
+        // Tabla de 256 entradas para no calcular powf por cada canal
+        let inv_gamma = 1.0 / gamma;
+        let mut table = [0.0f32; 256];
+        for (i, value) in table.iter_mut().enumerate() {
+            let c = i as f32 / 255.0;
+            *value = (c * exposure).min(1.0).powf(inv_gamma) * 255.0;
+        }
+
+        let factor = self.supersample_factor;
+        for (index, pixel) in self.buffer.iter_mut().enumerate() {
+            let offset = if dither {
+                let (x, y) = ((index % self.width) / factor, (index / self.width) / factor);
+                (BAYER_4X4[y % 4][x % 4] as f32 + 0.5) / 16.0 - 0.5
+            } else {
+                0.0
+            };
+            let [r, g, b] = [(*pixel >> 16) & 0xFF, (*pixel >> 8) & 0xFF, *pixel & 0xFF]
+                .map(|c| (table[c as usize] + offset).round().clamp(0.0, 255.0) as u32);
+            *pixel = (r << 16) | (g << 8) | b;
+        }
+    }
+
+    // Per-pixel grading on 0-1 channels: `brightness` is added, `contrast`
+    // scales around mid-gray (0.5) and `saturation` moves each channel away
+    // from (above 1) or toward (below 1) the pixel's luminance.
+    // (0.0, 1.0, 1.0) leaves the image unchanged.
+    pub fn color_grade(&mut self, brightness: f32, contrast: f32, saturation: f32) {
+        for pixel in self.buffer.iter_mut() {
+            let channels = [(*pixel >> 16) & 0xFF, (*pixel >> 8) & 0xFF, *pixel & 0xFF]
+                .map(|c| ((c as f32 / 255.0) + brightness - 0.5) * contrast + 0.5);
+            let luminance = 0.2126 * channels[0] + 0.7152 * channels[1] + 0.0722 * channels[2];
+            let [r, g, b] = channels
+                .map(|c| ((luminance + (c - luminance) * saturation) * 255.0).round().clamp(0.0, 255.0) as u32);
+            *pixel = (r << 16) | (g << 8) | b;
+        }
+    }
+
+    // Glow around bright pixels: bright pass at half resolution, separable
+    // Gaussian blur, then additive blend back. `radius` is in framebuffer pixels.
+    pub fn apply_bloom(&mut self, threshold: f32, radius: usize) {
+        let half_radius = radius / 2;
+        if half_radius == 0 {
+            return;
+        }
+
+        // Bright pass, promediando bloques de 2x2 para trabajar a media resolución
+        let (half_width, half_height) = (self.width.div_ceil(2), self.height.div_ceil(2));
+        let mut bright = vec![[0.0f32; 3]; half_width * half_height];
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let pixel = self.buffer[y * self.width + x];
+                if Color::from_hex(pixel).is_bright(threshold) {
+                    let rgb = [
+                        ((pixel >> 16) & 0xFF) as f32 / 255.0,
+                        ((pixel >> 8) & 0xFF) as f32 / 255.0,
+                        (pixel & 0xFF) as f32 / 255.0,
+                    ];
+                    let cell = &mut bright[(y / 2) * half_width + x / 2];
+                    for channel in 0..3 {
+                        cell[channel] += rgb[channel] * 0.25;
+                    }
+                }
+            }
+        }
+
+        let kernel = gaussian_kernel(half_radius);
+        let mut blurred = vec![[0.0f32; 3]; bright.len()];
+        blur_pass(&bright, &mut blurred, half_width, half_height, &kernel, (1, 0));
+        blur_pass(&blurred, &mut bright, half_width, half_height, &kernel, (0, 1));
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let glow = bright[(y / 2) * half_width + x / 2];
+                let index = y * self.width + x;
+                let pixel = self.buffer[index];
+                let r = ((pixel >> 16) & 0xFF) + (glow[0] * 255.0) as u32;
+                let g = ((pixel >> 8) & 0xFF) + (glow[1] * 255.0) as u32;
+                let b = (pixel & 0xFF) + (glow[2] * 255.0) as u32;
+                self.buffer[index] = (r.min(255) << 16) | (g.min(255) << 8) | b.min(255);
+            }
+        }
+    }
+
+    // Saved images are the resolved output: supersampled frames are box-filtered
+    // down to output_width() x output_height() first, as on screen
+    pub fn save_png(&self, path: &str) -> io::Result<()> {
+        let (width, height) = (self.output_width() as u32, self.output_height() as u32);
+        let image = RgbImage::from_raw(width, height, self.rgb_bytes())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "buffer size does not match framebuffer dimensions"))?;
+
+        image.save(path).map_err(io::Error::other)
+    }
+
+    // Binary PPM (P6): a text header followed by raw RGB bytes. Needs no
+    // image crate, and most tools can convert it later.
+    pub fn save_ppm(&self, path: &str) -> io::Result<()> {
+        let mut data = format!("P6\n{} {}\n255\n", self.output_width(), self.output_height()).into_bytes();
+        data.extend(self.rgb_bytes());
+        fs::write(path, data)
+    }
+
+    // Convertir cada pixel 0xRRGGBB de la imagen resuelta a bytes RGB, descartando el byte alto
+    fn rgb_bytes(&self) -> Vec<u8> {
+        let pixels = self.downsample();
+        let mut bytes = Vec::with_capacity(pixels.len() * 3);
+        for pixel in &pixels {
+            bytes.push(((pixel >> 16) & 0xFF) as u8);
+            bytes.push(((pixel >> 8) & 0xFF) as u8);
+            bytes.push((pixel & 0xFF) as u8);
+        }
+        bytes
+    }
+}
+
+// Normalized 1D Gaussian with 2 * radius + 1 taps
+fn gaussian_kernel(radius: usize) -> Vec<f32> {
+    let sigma = (radius as f32 / 2.0).max(1.0);
+    let mut kernel: Vec<f32> = (0..=2 * radius)
+        .map(|i| {
+            let offset = i as f32 - radius as f32;
+            (-(offset * offset) / (2.0 * sigma * sigma)).exp()
+        })
+        .collect();
+    let sum: f32 = kernel.iter().sum();
+    for weight in kernel.iter_mut() {
+        *weight /= sum;
+    }
+    kernel
+}
+
+// One direction of the separable blur; samples outside the image are clamped to the edge
+fn blur_pass(source: &[[f32; 3]], target: &mut [[f32; 3]], width: usize, height: usize, kernel: &[f32], direction: (usize, usize)) {
+    let radius = (kernel.len() / 2) as isize;
+    for y in 0..height {
+        for x in 0..width {
+            let mut sum = [0.0f32; 3];
+            for (i, weight) in kernel.iter().enumerate() {
+                let offset = i as isize - radius;
+                let sx = (x as isize + offset * direction.0 as isize).clamp(0, width as isize - 1) as usize;
+                let sy = (y as isize + offset * direction.1 as isize).clamp(0, height as isize - 1) as usize;
+                let sample = source[sy * width + sx];
+                for channel in 0..3 {
+                    sum[channel] += sample[channel] * weight;
+                }
+            }
+            target[y * width + x] = sum;
+        }
+    }
+}
+
+// A band of full-width rows borrowed from a Framebuffer, with its own slice
+// of the depth buffer. Coordinates are framebuffer pixels; writes outside
+// the band are ignored.
+pub struct FramebufferTile<'a> {
+    width: usize,
+    first_row: usize,
+    buffer: &'a mut [u32],
+    zbuffer: &'a mut [f32],
+}
+
+impl FramebufferTile<'_> {
+    // Framebuffer rows covered by this tile
+    pub fn rows(&self) -> Range<usize> {
+        self.first_row..self.first_row + self.buffer.len() / self.width
+    }
+
+    fn index(&self, x: usize, y: usize) -> Option<usize> {
+        if x < self.width && self.rows().contains(&y) {
+            Some((y - self.first_row) * self.width + x)
+        } else {
+            None
+        }
+    }
+
+    // Same as Framebuffer::depth_test, restricted to this tile
+    pub fn depth_test(&self, x: usize, y: usize, depth: f32) -> bool {
+        self.index(x, y).is_some_and(|index| self.zbuffer[index] > depth)
+    }
+
+    pub fn point(&mut self, x: usize, y: usize, depth: f32, color: u32) {
+        if let Some(index) = self.index(x, y) {
+            if self.zbuffer[index] > depth {
+                self.buffer[index] = color;
+                self.zbuffer[index] = depth;
+            }
+        }
+    }
+
+    pub fn blend_point(&mut self, x: usize, y: usize, depth: f32, color: &Color) {
+        if let Some(index) = self.index(x, y) {
+            if self.zbuffer[index] > depth {
+                let background = Color::from_hex(self.buffer[index]);
+                self.buffer[index] = background.blend_alpha(color).to_hex();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gray(value: u32) -> u32 {
+        (value << 16) | (value << 8) | value
+    }
+
+    // Gamma-corrected channel before rounding
+    fn exact_gamma(value: u32, gamma: f32) -> f32 {
+        (value as f32 / 255.0).powf(1.0 / gamma) * 255.0
+    }
+
+    #[test]
+    fn dithered_gradient_stays_within_one_lsb() {
+        // Mid-gray ramp, one level per column
+        let mut framebuffer = Framebuffer::new(64, 8);
+        for (index, pixel) in framebuffer.buffer.iter_mut().enumerate() {
+            *pixel = gray(96 + (index % 64) as u32);
+        }
+        framebuffer.apply_gamma(2.2, 1.0, true);
+
+        for (index, &pixel) in framebuffer.buffer.iter().enumerate() {
+            let expected = exact_gamma(96 + (index % 64) as u32, 2.2);
+            for channel in [pixel >> 16, (pixel >> 8) & 0xFF, pixel & 0xFF] {
+                assert!((channel as f32 - expected).abs() <= 1.0, "pixel {}: {} vs {}", index, channel, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn gamma_without_dither_matches_plain_rounding() {
+        let mut framebuffer = Framebuffer::new(256, 1);
+        for (value, pixel) in framebuffer.buffer.iter_mut().enumerate() {
+            *pixel = gray(value as u32);
+        }
+        framebuffer.apply_gamma(2.2, 1.0, false);
+
+        // What the pass produced before dithering existed: a u8 table
+        for (value, &pixel) in framebuffer.buffer.iter().enumerate() {
+            let expected = exact_gamma(value as u32, 2.2).round().clamp(0.0, 255.0) as u32;
+            assert_eq!(pixel, gray(expected), "value {}", value);
+        }
+    }
+
+    #[test]
+    fn dither_preserves_average_color() {
+        // A 4×4 block sees every Bayer offset once
+        for value in [40, 100, 128, 200] {
+            let mut framebuffer = Framebuffer::new(4, 4);
+            framebuffer.buffer.fill(gray(value));
+            framebuffer.apply_gamma(2.2, 1.0, true);
+
+            let mean = framebuffer.buffer.iter().map(|pixel| (pixel & 0xFF) as f32).sum::<f32>() / 16.0;
+            let expected = exact_gamma(value, 2.2);
+            assert!((mean - expected).abs() < 1.0 / 16.0, "value {}: mean {} vs {}", value, mean, expected);
+        }
+    }
+
+    #[test]
+    fn unit_exposure_with_identity_gamma_is_passthrough() {
+        let mut framebuffer = Framebuffer::new(256, 1);
+        for (value, pixel) in framebuffer.buffer.iter_mut().enumerate() {
+            let value = value as u32;
+            *pixel = (value << 16) | ((255 - value) << 8) | (value * 7 % 256);
+        }
+        let original = framebuffer.buffer.clone();
+        framebuffer.apply_gamma(1.0, 1.0, false);
+        assert_eq!(framebuffer.buffer, original);
+    }
+
+    #[test]
+    fn exposure_saturates_at_white() {
+        for dither in [false, true] {
+            let mut framebuffer = Framebuffer::new(256, 4);
+            for (index, pixel) in framebuffer.buffer.iter_mut().enumerate() {
+                *pixel = gray((index % 256) as u32);
+            }
+            framebuffer.apply_gamma(2.2, 4.0, dither);
+
+            for (index, &pixel) in framebuffer.buffer.iter().enumerate() {
+                // From 64 up, 4× exposure is already past 1.0
+                if index % 256 >= 64 {
+                    assert_eq!(pixel, gray(255), "value {}", index % 256);
+                }
+            }
+            // Without the dither offsets, brighter input never comes out
+            // darker, so nothing wrapped around below 64 either
+            if !dither {
+                let row = &framebuffer.buffer[..256];
+                assert!(row.windows(2).all(|pair| pair[0] & 0xFF <= pair[1] & 0xFF));
+            }
+        }
+    }
+
+    // Every 17th level of each channel, so the grid hits 0 and 255
+    fn color_grid() -> Framebuffer {
+        let mut framebuffer = Framebuffer::new(16, 16 * 16);
+        for (index, pixel) in framebuffer.buffer.iter_mut().enumerate() {
+            let (r, g, b) = ((index % 16) as u32 * 17, (index / 16 % 16) as u32 * 17, (index / 256) as u32 * 17);
+            *pixel = (r << 16) | (g << 8) | b;
+        }
+        framebuffer
+    }
+
+    #[test]
+    fn identity_color_grade_leaves_pixels_unchanged() {
+        let mut framebuffer = color_grid();
+        let original = framebuffer.buffer.clone();
+        framebuffer.color_grade(0.0, 1.0, 1.0);
+        assert!(framebuffer.buffer == original);
+    }
+
+    #[test]
+    fn color_grade_extremes_saturate() {
+        let mut framebuffer = color_grid();
+        framebuffer.color_grade(0.0, 1.0, 0.0);
+        for &pixel in &framebuffer.buffer {
+            assert!(pixel >> 16 == pixel & 0xFF && (pixel >> 8) & 0xFF == pixel & 0xFF, "{:06x} is not gray", pixel);
+        }
+
+        let mut framebuffer = color_grid();
+        framebuffer.color_grade(1.0, 1.0, 1.0);
+        assert!(framebuffer.buffer.iter().all(|&pixel| pixel == 0xFFFFFF));
+
+        let mut framebuffer = color_grid();
+        framebuffer.color_grade(0.0, 0.0, 3.0);
+        assert!(framebuffer.buffer.iter().all(|&pixel| pixel == 0x808080));
+    }
+
+    #[test]
+    fn gaussian_kernel_is_normalized_and_symmetric() {
+        for radius in [1, 2, 5, 16] {
+            let kernel = gaussian_kernel(radius);
+            assert_eq!(kernel.len(), 2 * radius + 1);
+            assert!((kernel.iter().sum::<f32>() - 1.0).abs() < 1e-5, "radius {}", radius);
+            for i in 0..radius {
+                assert_eq!(kernel[i], kernel[2 * radius - i]);
+                assert!(kernel[i] < kernel[i + 1]);
+            }
+        }
+    }
+
+    #[test]
+    fn bloom_keeps_black_black_and_only_adds_light() {
+        let mut framebuffer = Framebuffer::new(32, 24);
+        framebuffer.apply_bloom(0.0, 8);
+        assert!(framebuffer.buffer.iter().all(|&pixel| pixel == 0));
+
+        // A single bright dot spreads to its neighbours without dimming anything
+        let mut framebuffer = color_grid();
+        let center = framebuffer.buffer.len() / 2;
+        framebuffer.buffer[center] = 0xFFFFFF;
+        let original = framebuffer.buffer.clone();
+        framebuffer.apply_bloom(0.9, 8);
+        for (&before, &after) in original.iter().zip(&framebuffer.buffer) {
+            for shift in [16, 8, 0] {
+                assert!((after >> shift) & 0xFF >= (before >> shift) & 0xFF);
+            }
+        }
+        assert!(framebuffer.buffer != original);
+    }
+
+    #[test]
+    fn starfield_depends_only_on_its_seed() {
+        let sky = |seed: u64| {
+            let mut framebuffer = Framebuffer::new(64, 48);
+            framebuffer.draw_starfield(seed, 0.05);
+            framebuffer.buffer
+        };
+        assert!(sky(7) == sky(7));
+        assert!(sky(7) != sky(8));
+        assert!(sky(7).iter().any(|&pixel| pixel != 0));
+    }
+
+    #[test]
+    fn depth_test_only_passes_closer_fragments() {
+        let mut framebuffer = Framebuffer::new(4, 4);
+        assert!(framebuffer.depth_test(1, 1, 0.9));
+
+        framebuffer.set_current_color(0xFF0000);
+        framebuffer.point(1, 1, 0.5);
+        assert!(framebuffer.depth_test(1, 1, 0.4));
+        assert!(!framebuffer.depth_test(1, 1, 0.5));
+        assert!(!framebuffer.depth_test(1, 1, 0.6));
+
+        // A farther point is rejected, a closer one overwrites
+        framebuffer.set_current_color(0x00FF00);
+        framebuffer.point(1, 1, 0.7);
+        assert_eq!((framebuffer.get_pixel(1, 1), framebuffer.depth_at(1, 1)), (Some(0xFF0000), 0.5));
+        framebuffer.point(1, 1, 0.2);
+        assert_eq!((framebuffer.get_pixel(1, 1), framebuffer.depth_at(1, 1)), (Some(0x00FF00), 0.2));
+    }
+
+    #[test]
+    fn visualize_depth_maps_near_to_white_and_far_to_black() {
+        let mut framebuffer = Framebuffer::new(4, 1);
+        framebuffer.set_current_color(0xFF0000);
+        for (x, depth) in [0.1, 0.5, 0.9].into_iter().enumerate() {
+            framebuffer.point(x, 0, depth);
+        }
+        framebuffer.visualize_depth(0.1, 0.9);
+        assert_eq!(framebuffer.buffer, vec![0xFFFFFF, 0x7F7F7F, 0x000000, 0x000000]);
+    }
+
+    // Pixels set by drawing one line on a clear 8x8 framebuffer
+    fn line_pixels(x0: i32, y0: i32, x1: i32, y1: i32) -> Vec<(usize, usize)> {
+        let mut framebuffer = Framebuffer::new(8, 8);
+        framebuffer.set_current_color(0xFFFFFF);
+        framebuffer.line(x0, y0, x1, y1);
+        (0..8).flat_map(|y| (0..8).map(move |x| (x, y)))
+            .filter(|&(x, y)| framebuffer.get_pixel(x, y) == Some(0xFFFFFF))
+            .collect()
+    }
+
+    #[test]
+    fn lines_set_exactly_their_pixels_in_both_directions() {
+        let horizontal: Vec<_> = (1..=6).map(|x| (x, 2)).collect();
+        assert_eq!(line_pixels(1, 2, 6, 2), horizontal);
+        assert_eq!(line_pixels(6, 2, 1, 2), horizontal);
+
+        let vertical: Vec<_> = (0..=7).map(|y| (3, y)).collect();
+        assert_eq!(line_pixels(3, 0, 3, 7), vertical);
+        assert_eq!(line_pixels(3, 7, 3, 0), vertical);
+
+        let diagonal: Vec<_> = (1..=5).map(|i| (i, i)).collect();
+        assert_eq!(line_pixels(1, 1, 5, 5), diagonal);
+        assert_eq!(line_pixels(5, 5, 1, 1), diagonal);
+    }
+
+    #[test]
+    fn lines_are_clipped_to_the_framebuffer() {
+        // Enters at the top-left corner and leaves at the bottom-right one
+        let clipped: Vec<_> = (0..=7).map(|i| (i, i)).collect();
+        assert_eq!(line_pixels(-3, -3, 12, 12), clipped);
+        assert!(line_pixels(-5, 1, -1, 6).is_empty());
+        assert!(line_pixels(2, 9, 6, 20).is_empty());
+    }
+
+    fn temp_path(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!("framebuffer_test_{}_{}", std::process::id(), name));
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn supersampled_frames_are_saved_at_output_resolution() {
+        // 2x1 output at 2x SSAA: a red block and a half-white block
+        let mut framebuffer = Framebuffer::new_supersampled(2, 1, 2);
+        framebuffer.buffer = vec![
+            0xFF0000, 0xFF0000, 0xFFFFFF, 0x000000,
+            0xFF0000, 0xFF0000, 0x000000, 0xFFFFFF,
+        ];
+        let path = temp_path("ssaa.ppm");
+        framebuffer.save_ppm(&path).unwrap();
+        let data = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let header = b"P6\n2 1\n255\n";
+        assert_eq!(&data[..header.len()], header);
+        assert_eq!(&data[header.len()..], &[255, 0, 0, 127, 127, 127]);
+    }
+
+    #[test]
+    fn png_round_trip_keeps_pixel_colors() {
+        let mut framebuffer = Framebuffer::new(4, 3);
+        framebuffer.buffer[2 * 4 + 1] = 0x12AB34;
+        let path = temp_path("round_trip.png");
+        framebuffer.save_png(&path).unwrap();
+        let image = image::open(&path).unwrap().to_rgb8();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(image.dimensions(), (4, 3));
+        assert_eq!(image.get_pixel(1, 2).0, [0x12, 0xAB, 0x34]);
+        assert_eq!(image.get_pixel(0, 0).0, [0, 0, 0]);
+    }
+
+    #[test]
+    fn ppm_has_a_p6_header_and_raw_rgb_rows() {
+        let mut framebuffer = Framebuffer::new(3, 2);
+        framebuffer.buffer[0] = 0xFF8000;
+        framebuffer.buffer[3 + 2] = 0x0102FE;
+        let path = temp_path("plain.ppm");
+        framebuffer.save_ppm(&path).unwrap();
+        let data = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let header = b"P6\n3 2\n255\n";
+        assert_eq!(&data[..header.len()], header);
+        let pixels = &data[header.len()..];
+        assert_eq!(pixels.len(), 3 * 2 * 3);
+        assert_eq!(&pixels[0..3], &[0xFF, 0x80, 0x00]);
+        assert_eq!(&pixels[3..6], &[0, 0, 0]);
+        assert_eq!(&pixels[15..18], &[0x01, 0x02, 0xFE]);
+    }
 }
@@ -0,0 +1,609 @@
+// Núcleo del renderizador: los módulos, los uniforms y el pipeline `render`.
+// Lo usan el binario (src/main.rs) y los benchmarks de benches/.
+use nalgebra_glm::{Vec3, Mat4};
+use std::collections::HashMap;
+use rayon::prelude::*;
+
+pub mod framebuffer;
+pub mod triangle;
+pub mod vertex;
+pub mod obj;
+pub mod color;
+pub mod fragment;
+pub mod shaders;
+pub mod camera;
+pub mod planet;
+pub mod clip;
+pub mod scene;
+pub mod ring;
+pub mod timer;
+pub mod light;
+pub mod font;
+pub mod texture;
+pub mod transform;
+pub mod tour;
+pub mod picking;
+
+use framebuffer::Framebuffer;
+use vertex::Vertex;
+use triangle::{triangle_in_rows, is_back_facing, vertex_fragment};
+use shaders::{vertex_shader, fragment_shader};
+use fragment::Fragment;
+use fastnoise_lite::{FastNoiseLite, NoiseType, FractalType};
+use planet::PlanetType;
+use clip::clip_triangle;
+use color::Color;
+use light::Light;
+use texture::Texture;
+
+pub struct Uniforms {
+    pub model_matrix: Mat4,
+    pub view_matrix: Mat4,
+    pub projection_matrix: Mat4,
+    pub viewport_matrix: Mat4,
+    pub time: u32,
+    pub noise: FastNoiseLite,
+    pub cull_backfaces: bool,
+    pub toon_shading: bool,
+    pub show_normals: bool,
+    pub coverage_aa: bool,
+    pub shading_mode: ShadingMode,
+    // Se aplica a todo lo que dibuja `render`; cero para las superficies normales
+    pub depth_bias: DepthBias,
+    pub show_orbits: bool,
+    // Semilla del cuerpo que se está dibujando, para los ruidos fijos del shader
+    pub seed: u64,
+    // Mapa equirectangular de la Tierra; sin él se usa el shader procedural
+    pub earth_texture: Option<Texture>,
+    pub lights: Vec<Light>,
+    // Posición del ojo en el mundo; la dirección de vista se calcula por fragmento
+    pub camera_position: Vec3,
+}
+
+pub struct CelestialBody {
+    // Posición en el mundo; la calcula update_world_positions a partir de la
+    // posición local y la del padre
+    pub position: Vec3,
+    // Posición relativa al padre (o al origen si no tiene)
+    pub local_position: Vec3,
+    // Índice del cuerpo alrededor del que orbita, si lo hay
+    pub parent: Option<usize>,
+    pub scale: f32,
+    pub rotation: Vec3,
+    pub shader_type: PlanetType,
+    pub orbit_radius: f32,
+    pub orbit_speed: f32,
+    pub orbit_angle: f32,
+    pub rotation_speed: f32,
+    pub axial_tilt: f32,
+    // Semilla del ruido del cuerpo; con 0 se ve igual que siempre
+    pub seed: u64,
+}
+
+// Dónde se evalúa la iluminación (tecla F). Flat usa una sola normal por
+// triángulo y da un aspecto facetado; Gouraud sombrea solo los vértices e
+// interpola el color, lo que es mucho más barato pero emborrona el ruido de
+// los shaders y los brillos especulares; Phong interpola la normal y sombrea
+// cada fragmento, que es lo más caro y lo más fiel.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ShadingMode {
+    Flat,
+    Gouraud,
+    Phong,
+}
+
+// Desplazamiento de profundidad hacia la cámara para geometría que se dibuja
+// encima de otra casi en el mismo sitio (los anillos), para que
+// no parpadee por z-fighting. `constant` está en unidades de profundidad NDC
+// y `slope_scale` multiplica la pendiente máxima de profundidad del
+// triángulo por píxel, porque los triángulos inclinados necesitan más margen.
+#[derive(Clone, Copy, Default)]
+pub struct DepthBias {
+    pub constant: f32,
+    pub slope_scale: f32,
+}
+
+// Cada tipo de planeta usa el ruido que mejor va con su textura; los que
+// no tienen uno propio usan el de nubes
+pub fn noise_for(planet_type: &PlanetType, seed: u64) -> FastNoiseLite {
+    let mut noise = match planet_type {
+        PlanetType::FirePlanet => create_lava_noise(),
+        PlanetType::RockyPlanet | PlanetType::Asteroid | PlanetType::Moon => create_ground_noise(),
+        PlanetType::CrystalPlanet => create_cell_noise(),
+        PlanetType::GasGiant => create_gas_noise(),
+        _ => create_cloud_noise(),
+    };
+    // La semilla del cuerpo desplaza la de cada tipo; FastNoiseLite solo usa 32 bits
+    noise.set_seed(Some(noise.seed.wrapping_add(seed as i32)));
+    noise
+}
+
+pub fn create_cloud_noise() -> FastNoiseLite {
+    let mut noise = FastNoiseLite::with_seed(1337);
+    noise.set_noise_type(Some(NoiseType::OpenSimplex2));
+    noise
+}
+
+fn create_cell_noise() -> FastNoiseLite {
+    let mut noise = FastNoiseLite::with_seed(1337);
+    noise.set_noise_type(Some(NoiseType::Cellular));
+    noise.set_frequency(Some(0.1));
+    noise
+}
+
+fn create_ground_noise() -> FastNoiseLite {
+    let mut noise = FastNoiseLite::with_seed(1337);
+    
+    // Use FBm fractal type to layer multiple octaves of noise
+    noise.set_noise_type(Some(NoiseType::Cellular)); // Cellular noise for cracks
+    noise.set_fractal_type(Some(FractalType::FBm));  // Fractal Brownian Motion
+    noise.set_fractal_octaves(Some(5));              // More octaves = more detail
+    noise.set_fractal_lacunarity(Some(2.0));         // Lacunarity controls frequency scaling
+    noise.set_fractal_gain(Some(0.5));               // Gain controls amplitude scaling
+    noise.set_frequency(Some(0.05));                 // Lower frequency for larger features
+
+    noise
+}
+
+fn create_gas_noise() -> FastNoiseLite {
+    let mut noise = FastNoiseLite::with_seed(2024);
+
+    // FBm suave para la turbulencia de las bandas; la escala la pone el shader
+    noise.set_noise_type(Some(NoiseType::OpenSimplex2));
+    noise.set_fractal_type(Some(FractalType::FBm));
+    noise.set_fractal_octaves(Some(4));
+    noise.set_fractal_lacunarity(Some(2.0));
+    noise.set_fractal_gain(Some(0.5));
+    noise.set_frequency(Some(1.0));
+
+    noise
+}
+
+fn create_lava_noise() -> FastNoiseLite {
+    let mut noise = FastNoiseLite::with_seed(42);
+    
+    // Use FBm for multi-layered noise, giving a "turbulent" feel
+    noise.set_noise_type(Some(NoiseType::Perlin));  // Perlin noise for smooth, natural texture
+    noise.set_fractal_type(Some(FractalType::FBm)); // FBm for layered detail
+    noise.set_fractal_octaves(Some(6));             // High octaves for rich detail
+    noise.set_fractal_lacunarity(Some(2.0));        // Higher lacunarity = more contrast between layers
+    noise.set_fractal_gain(Some(0.5));              // Higher gain = more influence of smaller details
+    noise.set_frequency(Some(0.002));                // Low frequency = large features
+    
+    noise
+}
+
+// Fragmento ya sombreado: (x, y, profundidad, color, cobertura)
+type ShadedFragment = (usize, usize, f32, Color, f32);
+
+// Filas del framebuffer por tile. Con tiles más bajos el trabajo se reparte
+// mejor entre hilos cuando un cuerpo ocupa pocas filas de la pantalla, a
+// cambio de recorrer la lista de triángulos más veces.
+const TILE_ROWS: usize = 16;
+
+// Buffers intermedios de `render`. Se vacían en cada llamada pero conservan
+// su capacidad, así que al reutilizar uno para todos los cuerpos y frames el
+// pipeline deja de reservar memoria una vez que los buffers alcanzan el
+// tamaño del cuerpo más grande en pantalla.
+#[derive(Default)]
+pub struct RenderScratch {
+    transformed_vertices: Vec<Vertex>,
+    clipped: Vec<[Vertex; 3]>,
+    triangles: Vec<[Vertex; 3]>,
+    tiles: Vec<TileScratch>,
+}
+
+// Buffers de un tile; cada hilo usa solo los del tile que está dibujando
+#[derive(Default)]
+struct TileScratch {
+    fragments: Vec<Fragment>,
+    shaded: Vec<ShadedFragment>,
+    partial_pixels: HashMap<(usize, usize), (Color, f32, f32)>,
+}
+
+pub fn render(framebuffer: &mut Framebuffer, uniforms: &Uniforms, vertex_array: &[Vertex], planet_type: &PlanetType, scratch: &mut RenderScratch) {
+    let RenderScratch { transformed_vertices, clipped, triangles, tiles } = scratch;
+
+    // Vertex Shader Stage
+    transformed_vertices.clear();
+    transformed_vertices.extend(vertex_array.iter().map(|vertex| vertex_shader(vertex, uniforms)));
+
+    // Gouraud: the full fragment shader runs once per vertex and its result
+    // replaces the vertex color, which the rasterizer then interpolates
+    if uniforms.shading_mode == ShadingMode::Gouraud {
+        transformed_vertices.par_iter_mut().for_each(|vertex| {
+            vertex.color = fragment_shader(&vertex_fragment(vertex), uniforms, planet_type);
+        });
+    }
+
+    // Primitive Assembly Stage
+    triangles.clear();
+    for i in (0..transformed_vertices.len()).step_by(3) {
+        if i + 2 < transformed_vertices.len() {
+            // Frustum clipping against all six planes
+            clipped.clear();
+            clip_triangle(
+                &transformed_vertices[i],
+                &transformed_vertices[i + 1],
+                &transformed_vertices[i + 2],
+                &uniforms.viewport_matrix,
+                clipped,
+            );
+
+            for mut tri in clipped.drain(..) {
+                // Back-face culling (after clipping so screen positions are valid)
+                if uniforms.cull_backfaces && is_back_facing(&tri[0], &tri[1], &tri[2]) {
+                    continue;
+                }
+                if uniforms.depth_bias.constant != 0.0 || uniforms.depth_bias.slope_scale != 0.0 {
+                    apply_depth_bias(&mut tri, uniforms.depth_bias);
+                }
+                // Flat: every corner gets the face's average normal
+                if uniforms.shading_mode == ShadingMode::Flat {
+                    let face_normal = (tri[0].transformed_normal + tri[1].transformed_normal + tri[2].transformed_normal).normalize();
+                    for vertex in tri.iter_mut() {
+                        vertex.transformed_normal = face_normal;
+                    }
+                }
+                triangles.push(tri);
+            }
+        }
+    }
+
+    // Tiled Rasterization, Fragment Processing and Output Merging
+    // The framebuffer is split into bands of TILE_ROWS rows, each drawn on its
+    // own thread into its own slice of the color and depth buffers. Every
+    // pixel belongs to exactly one tile and a tile sees its fragments in the
+    // same order as a single pass over the whole screen, so the result is
+    // identical to drawing the mesh serially.
+    let width = framebuffer.width;
+    let tile_count = framebuffer.height.div_ceil(TILE_ROWS);
+    if tiles.len() < tile_count {
+        tiles.resize_with(tile_count, TileScratch::default);
+    }
+    let triangles = &*triangles;
+
+    framebuffer
+        .tiles_mut(TILE_ROWS)
+        .zip(tiles.par_iter_mut())
+        .for_each(|(mut tile, TileScratch { fragments, shaded, partial_pixels })| {
+            let rows = tile.rows();
+
+            // Rasterization Stage
+            fragments.clear();
+            for tri in triangles.iter() {
+                let min_y = tri.iter().map(|vertex| vertex.transformed_position.y).fold(f32::INFINITY, f32::min);
+                let max_y = tri.iter().map(|vertex| vertex.transformed_position.y).fold(f32::NEG_INFINITY, f32::max);
+                if max_y < rows.start as f32 || min_y.floor() >= rows.end as f32 {
+                    continue;
+                }
+                triangle_in_rows(&tri[0], &tri[1], &tri[2], width, rows.clone(), uniforms.coverage_aa, fragments);
+            }
+
+            // Fragment Processing Stage
+            // Fragments already hidden by earlier draws are rejected before shading.
+            // Every fragment of the tile is shaded before any write, so the test
+            // sees the same depths as before this mesh; the writes below repeat it,
+            // so overlap within this mesh stays correct.
+            shaded.clear();
+            for fragment in fragments.iter() {
+                let Some((x, y)) = fragment_pixel(fragment) else {
+                    continue;
+                };
+                if tile.depth_test(x, y, fragment.depth) {
+                    // Apply fragment shader (already applied per vertex with Gouraud)
+                    let shaded_color = if uniforms.shading_mode == ShadingMode::Gouraud {
+                        fragment.color
+                    } else {
+                        fragment_shader(fragment, uniforms, planet_type)
+                    };
+                    shaded.push((x, y, fragment.depth, shaded_color, fragment.coverage));
+                }
+            }
+
+            // Píxeles de borde con antialiasing por cobertura: (color promedio, cobertura total, profundidad mínima)
+            partial_pixels.clear();
+
+            for &(x, y, depth, color, coverage) in shaded.iter() {
+                if coverage < 1.0 {
+                    let entry = partial_pixels.entry((x, y)).or_insert((color, 0.0, depth));
+                    entry.1 += coverage;
+                    entry.0 = entry.0.lerp(&color, coverage / entry.1);
+                    entry.2 = entry.2.min(depth);
+                    continue;
+                }
+
+                if color.is_opaque() {
+                    tile.point(x, y, depth, color.to_hex());
+                } else {
+                    tile.blend_point(x, y, depth, &color);
+                }
+            }
+
+            // Resolución de la cobertura. Los triángulos de la misma malla que
+            // comparten un borde suman su cobertura, así que el píxel queda opaco y
+            // escribe profundidad como uno interior (sin costuras). En la silueta la
+            // cobertura es parcial: el color se mezcla con lo que ya hay detrás y no
+            // se escribe profundidad, igual que los fragmentos transparentes; si
+            // después se dibuja algo detrás, pisa ese borde suavizado.
+            for ((x, y), (color, coverage, depth)) in partial_pixels.drain() {
+                if coverage >= 1.0 && color.is_opaque() {
+                    tile.point(x, y, depth, color.to_hex());
+                } else {
+                    let alpha = (color.alpha() as f32 * coverage.min(1.0)).round() as u8;
+                    tile.blend_point(x, y, depth, &color.with_alpha(alpha));
+                }
+            }
+        });
+}
+
+// Píxel donde escribe un fragmento. `as usize` satura los negativos a 0, así
+// que se descartan antes de convertir para no pintar en la primera fila o columna
+fn fragment_pixel(fragment: &Fragment) -> Option<(usize, usize)> {
+    if fragment.position.x < 0.0 || fragment.position.y < 0.0 {
+        return None;
+    }
+    Some((fragment.position.x as usize, fragment.position.y as usize))
+}
+
+// Polygon offset: the whole triangle moves toward the camera by the same
+// amount, so the offset can be applied to the vertices instead of to every
+// fragment. The slope term uses the screen-space depth gradient of the plane.
+fn apply_depth_bias(tri: &mut [Vertex; 3], bias: DepthBias) {
+    let a = tri[0].transformed_position;
+    let e1 = tri[1].transformed_position - a;
+    let e2 = tri[2].transformed_position - a;
+    let det = e1.x * e2.y - e2.x * e1.y;
+    let max_slope = if det.abs() > f32::EPSILON {
+        let dz_dx = (e1.z * e2.y - e2.z * e1.y) / det;
+        let dz_dy = (e2.z * e1.x - e1.z * e2.x) / det;
+        dz_dx.abs().max(dz_dy.abs())
+    } else {
+        0.0
+    };
+
+    let offset = bias.constant + bias.slope_scale * max_slope;
+    for vertex in tri.iter_mut() {
+        vertex.transformed_position.z -= offset;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use triangle::triangle;
+    use nalgebra_glm::Vec2;
+
+    const WIDTH: usize = 160;
+    // No es múltiplo de TILE_ROWS, así que el último tile es más bajo
+    const HEIGHT: usize = 120;
+    const SPHERE_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/models/smooth_sphere.obj");
+
+    fn test_uniforms(planet_type: &PlanetType, position: Vec3, scale: f32) -> Uniforms {
+        Uniforms {
+            model_matrix: transform::model(position, scale, Vec3::new(0.3, 0.5, 0.0)),
+            view_matrix: transform::view(Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0)),
+            projection_matrix: transform::perspective(75.0, WIDTH as f32, HEIGHT as f32, 0.1, 1000.0),
+            viewport_matrix: transform::viewport(WIDTH as f32, HEIGHT as f32),
+            time: 30,
+            noise: noise_for(planet_type, 0),
+            cull_backfaces: true,
+            toon_shading: false,
+            show_normals: false,
+            coverage_aa: false,
+            shading_mode: ShadingMode::Phong,
+            depth_bias: DepthBias::default(),
+            show_orbits: false,
+            seed: 0,
+            earth_texture: None,
+            lights: vec![Light::new(Vec3::new(-20.0, 10.0, 20.0), Color::new(255, 245, 225), 1.0)],
+            camera_position: Vec3::new(0.0, 0.0, 5.0),
+        }
+    }
+
+    // El pipeline de `render` en una sola pasada: `triangle` sobre todas las
+    // filas y escrituras en orden directamente sobre el framebuffer
+    fn render_serial(framebuffer: &mut Framebuffer, uniforms: &Uniforms, vertex_array: &[Vertex], planet_type: &PlanetType) {
+        let mut transformed: Vec<Vertex> = vertex_array.iter().map(|vertex| vertex_shader(vertex, uniforms)).collect();
+        if uniforms.shading_mode == ShadingMode::Gouraud {
+            for vertex in transformed.iter_mut() {
+                vertex.color = fragment_shader(&vertex_fragment(vertex), uniforms, planet_type);
+            }
+        }
+
+        let mut triangles = Vec::new();
+        for corners in transformed.chunks_exact(3) {
+            let mut clipped = Vec::new();
+            clip_triangle(&corners[0], &corners[1], &corners[2], &uniforms.viewport_matrix, &mut clipped);
+            for mut tri in clipped {
+                if uniforms.cull_backfaces && is_back_facing(&tri[0], &tri[1], &tri[2]) {
+                    continue;
+                }
+                if uniforms.depth_bias.constant != 0.0 || uniforms.depth_bias.slope_scale != 0.0 {
+                    apply_depth_bias(&mut tri, uniforms.depth_bias);
+                }
+                if uniforms.shading_mode == ShadingMode::Flat {
+                    let face_normal = (tri[0].transformed_normal + tri[1].transformed_normal + tri[2].transformed_normal).normalize();
+                    for vertex in tri.iter_mut() {
+                        vertex.transformed_normal = face_normal;
+                    }
+                }
+                triangles.push(tri);
+            }
+        }
+
+        let mut fragments = Vec::new();
+        for tri in &triangles {
+            triangle(&tri[0], &tri[1], &tri[2], framebuffer.width, framebuffer.height, uniforms.coverage_aa, &mut fragments);
+        }
+
+        let mut shaded = Vec::new();
+        for fragment in &fragments {
+            let Some((x, y)) = fragment_pixel(fragment) else {
+                continue;
+            };
+            if x < framebuffer.width && y < framebuffer.height && framebuffer.depth_test(x, y, fragment.depth) {
+                let color = if uniforms.shading_mode == ShadingMode::Gouraud {
+                    fragment.color
+                } else {
+                    fragment_shader(fragment, uniforms, planet_type)
+                };
+                shaded.push((x, y, fragment.depth, color, fragment.coverage));
+            }
+        }
+
+        let mut partial_pixels: HashMap<(usize, usize), (Color, f32, f32)> = HashMap::new();
+        for (x, y, depth, color, coverage) in shaded {
+            if coverage < 1.0 {
+                let entry = partial_pixels.entry((x, y)).or_insert((color, 0.0, depth));
+                entry.1 += coverage;
+                entry.0 = entry.0.lerp(&color, coverage / entry.1);
+                entry.2 = entry.2.min(depth);
+            } else if color.is_opaque() {
+                framebuffer.set_current_color(color.to_hex());
+                framebuffer.point(x, y, depth);
+            } else {
+                framebuffer.blend_point(x, y, depth, &color);
+            }
+        }
+        for ((x, y), (color, coverage, depth)) in partial_pixels {
+            if coverage >= 1.0 && color.is_opaque() {
+                framebuffer.set_current_color(color.to_hex());
+                framebuffer.point(x, y, depth);
+            } else {
+                let alpha = (color.alpha() as f32 * coverage.min(1.0)).round() as u8;
+                framebuffer.blend_point(x, y, depth, &color.with_alpha(alpha));
+            }
+        }
+    }
+
+    #[test]
+    fn tiled_render_matches_serial_render() {
+        let sphere = obj::Obj::load(SPHERE_PATH, false).expect("smooth_sphere.obj").get_vertex_array();
+        // Un sol grande, una luna delante que lo tapa en parte y una capa de
+        // nubes transparente por encima, que cruzan varios tiles
+        let draws = [
+            (PlanetType::Sun, Vec3::new(0.0, 0.0, 0.0), 2.0),
+            (PlanetType::Moon, Vec3::new(0.8, -0.4, 1.5), 0.6),
+            (PlanetType::CloudShell, Vec3::new(0.0, 0.0, 0.0), 2.1),
+        ];
+
+        for (shading_mode, coverage_aa) in [
+            (ShadingMode::Phong, false),
+            (ShadingMode::Phong, true),
+            (ShadingMode::Flat, false),
+            (ShadingMode::Gouraud, true),
+        ] {
+            let mut tiled = Framebuffer::new(WIDTH, HEIGHT);
+            let mut serial = Framebuffer::new(WIDTH, HEIGHT);
+            let mut scratch = RenderScratch::default();
+
+            for (planet_type, position, scale) in &draws {
+                let mut uniforms = test_uniforms(planet_type, *position, *scale);
+                uniforms.shading_mode = shading_mode;
+                uniforms.coverage_aa = coverage_aa;
+                render(&mut tiled, &uniforms, &sphere, planet_type, &mut scratch);
+                render_serial(&mut serial, &uniforms, &sphere, planet_type);
+            }
+
+            assert!(tiled.depth_buffer().iter().any(|depth| depth.is_finite()));
+            assert!(tiled.buffer == serial.buffer, "color differs with {:?}, coverage AA {}", shading_mode, coverage_aa);
+            let depth_bits = |framebuffer: &Framebuffer| framebuffer.depth_buffer().iter().map(|depth| depth.to_bits()).collect::<Vec<_>>();
+            assert!(depth_bits(&tiled) == depth_bits(&serial), "depth differs with {:?}, coverage AA {}", shading_mode, coverage_aa);
+        }
+    }
+
+    // La misma malla dibujada dos veces queda a la misma profundidad, y sin
+    // desplazamiento la segunda pierde el test de profundidad en todos los
+    // píxeles. Con el desplazamiento de los anillos gana en todos.
+    #[test]
+    fn biased_overlay_wins_over_a_coplanar_base() {
+        let sphere = obj::Obj::load(SPHERE_PATH, false).expect("smooth_sphere.obj").get_vertex_array();
+        let (base, overlay) = (PlanetType::Sun, PlanetType::Moon);
+        let draw = |draws: &[(&PlanetType, DepthBias)]| {
+            let mut framebuffer = Framebuffer::new(WIDTH, HEIGHT);
+            let mut scratch = RenderScratch::default();
+            for (planet_type, depth_bias) in draws {
+                let mut uniforms = test_uniforms(planet_type, Vec3::new(0.0, 0.0, 0.0), 2.0);
+                uniforms.depth_bias = *depth_bias;
+                render(&mut framebuffer, &uniforms, &sphere, planet_type, &mut scratch);
+            }
+            framebuffer.buffer
+        };
+        let bias = DepthBias { constant: 1e-6, slope_scale: 1.0 };
+
+        let base_alone = draw(&[(&base, DepthBias::default())]);
+        let overlay_alone = draw(&[(&overlay, bias)]);
+        assert!(base_alone.iter().zip(&overlay_alone).filter(|(a, b)| a != b).count() > 1000);
+
+        assert!(draw(&[(&base, DepthBias::default()), (&overlay, DepthBias::default())]) == base_alone);
+        assert!(draw(&[(&base, DepthBias::default()), (&overlay, bias)]) == overlay_alone);
+    }
+
+    // Triángulo en pantalla de 10 px de lado cuya profundidad crece `slope`
+    // por píxel en x
+    fn sloped_triangle(slope: f32) -> [Vertex; 3] {
+        [(0.0, 0.0), (10.0, 0.0), (0.0, 10.0)].map(|(x, y)| {
+            let mut vertex = Vertex::new(Vec3::new(x, y, 0.0), Vec3::new(0.0, 0.0, 1.0), Vec2::new(0.0, 0.0));
+            vertex.transformed_position = Vec3::new(x, y, 0.5 + slope * x);
+            vertex
+        })
+    }
+
+    fn bias_offset(mut tri: [Vertex; 3], bias: DepthBias) -> Vec<f32> {
+        let before: Vec<f32> = tri.iter().map(|v| v.transformed_position.z).collect();
+        apply_depth_bias(&mut tri, bias);
+        tri.iter().zip(before).map(|(v, z)| z - v.transformed_position.z).collect()
+    }
+
+    #[test]
+    fn constant_depth_bias_shifts_by_exactly_constant() {
+        let bias = DepthBias { constant: 1e-3, slope_scale: 0.0 };
+        let mut tri = sloped_triangle(0.01);
+        let expected: Vec<f32> = tri.iter().map(|v| v.transformed_position.z - bias.constant).collect();
+        apply_depth_bias(&mut tri, bias);
+        for (vertex, z) in tri.iter().zip(expected) {
+            assert_eq!(vertex.transformed_position.z, z);
+        }
+    }
+
+    #[test]
+    fn slope_depth_bias_grows_with_tilt() {
+        let bias = DepthBias { constant: 0.0, slope_scale: 1.0 };
+        let offsets: Vec<f32> = [0.0, 0.001, 0.004].iter()
+            .map(|&slope| bias_offset(sloped_triangle(slope), bias)[0])
+            .collect();
+        assert_eq!(offsets[0], 0.0);
+        assert!((offsets[1] - 0.001).abs() < 1e-6, "{:?}", offsets);
+        assert!((offsets[2] - 0.004).abs() < 1e-6, "{:?}", offsets);
+        // El mismo desplazamiento para los tres vértices
+        let offsets = bias_offset(sloped_triangle(0.004), bias);
+        assert!(offsets.iter().all(|offset| (offset - offsets[0]).abs() < 1e-6));
+    }
+
+    #[test]
+    fn degenerate_triangle_gets_no_slope_bias() {
+        let bias = DepthBias { constant: 1e-3, slope_scale: 100.0 };
+        let mut tri = sloped_triangle(0.01);
+        // Los tres vértices sobre la misma línea: no hay plano del que sacar la pendiente
+        tri[2].transformed_position = Vec3::new(5.0, 0.0, 0.9);
+        for offset in bias_offset(tri, bias) {
+            assert!((offset - bias.constant).abs() < 1e-6, "{}", offset);
+        }
+    }
+
+    fn fragment_at(x: f32, y: f32) -> Fragment {
+        let zero = Vec3::new(0.0, 0.0, 0.0);
+        Fragment::new(Vec3::new(x, y, 0.5), zero, zero, Vec2::new(0.0, 0.0), Color::new(255, 255, 255), 1.0)
+    }
+
+    #[test]
+    fn negative_fragments_write_no_pixel() {
+        // Sin el descarte, (-0.5, 3.2) caería en la columna 0 y (2.7, -1.0) en la fila 0
+        assert_eq!(fragment_pixel(&fragment_at(-0.5, 3.2)), None);
+        assert_eq!(fragment_pixel(&fragment_at(2.7, -1.0)), None);
+        assert_eq!(fragment_pixel(&fragment_at(-4.0, -4.0)), None);
+        assert_eq!(fragment_pixel(&fragment_at(2.7, 3.2)), Some((2, 3)));
+        assert_eq!(fragment_pixel(&fragment_at(0.0, 0.0)), Some((0, 0)));
+    }
+}
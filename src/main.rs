@@ -1,215 +1,484 @@
-use nalgebra_glm::{Vec3, Mat4, look_at, perspective};
-use minifb::{Key, Window, WindowOptions};
+use nalgebra_glm::{Vec3, Vec4, Mat4, translation};
+use minifb::{Key, KeyRepeat, Window, WindowOptions, MouseMode, MouseButton};
 use std::f32::consts::PI;
+use std::fs;
+use std::fmt::Write;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-mod framebuffer;
-mod triangle;
-mod vertex;
-mod obj;
-mod color;
-mod fragment;
-mod shaders;
-mod camera;
-mod planet;
+use solar_system::{framebuffer, vertex, obj, color, camera, planet, scene, ring, timer, light, texture, transform, tour, picking};
+use solar_system::{Uniforms, CelestialBody, ShadingMode, DepthBias, RenderScratch, render, noise_for, create_cloud_noise};
 
 use framebuffer::Framebuffer;
 use vertex::Vertex;
 use obj::Obj;
-use camera::Camera;
-use triangle::triangle;
-use shaders::{vertex_shader, fragment_shader};
-use fastnoise_lite::{FastNoiseLite, NoiseType, FractalType};
+use camera::{Camera, CameraPreset, frustum_planes, sphere_in_frustum};
 use planet::PlanetType;
+use color::Color;
+use ring::create_ring_mesh;
+use timer::FrameTimer;
+use light::Light;
+use texture::Texture;
+use tour::Tour;
+use picking::{screen_ray, pick_body};
+
+// Los shaders se ajustaron con `time` avanzando una unidad por frame a 60 FPS
+const TIME_TICKS_PER_SECOND: f32 = 60.0;
+// Un frame muy lento (p. ej. al mover la ventana) no debe teletransportar las órbitas
+const MAX_DELTA_TIME: f32 = 0.1;
+// Factor de supersampling (SSAA). Con 2 se sombrean 4 veces más fragmentos,
+// así que 1 es lo más rápido y 3 o más solo vale la pena para capturas.
+const SSAA_FACTOR: usize = 2;
+// Escala de la resolución interna respecto a la ventana (teclas [ y ]). A
+// diferencia del SSAA sirve para ganar velocidad: se renderiza a menos
+// resolución y se estira al presentar.
+const MIN_RENDER_SCALE: f32 = 0.25;
+const RENDER_SCALE_STEP: f32 = 0.125;
+// Con la escala automática (tecla R) se busca mantener el frame por debajo de
+// este tiempo; tras cada ajuste se espera a que el promedio se estabilice
+const FRAME_TIME_BUDGET: f32 = 1.0 / 30.0;
+const AUTO_SCALE_COOLDOWN: f32 = 1.0;
+// Gradación de color final: (brillo, contraste, saturación); (0, 1, 1) no cambia nada
+const COLOR_GRADE: (f32, f32, f32) = (0.0, 1.1, 1.15);
+// Campo de visión vertical inicial: 4/3 rad, el mismo que se veía en la
+// ventana de 800x600 cuando el aspecto y el FOV iban intercambiados
+const DEFAULT_FOV: f32 = 76.4;
+// Rango del campo de visión en grados y cuánto cambia con las teclas , y .
+const MIN_FOV: f32 = 10.0;
+const MAX_FOV: f32 = 150.0;
+const FOV_STEP: f32 = 5.0;
+// Sesgo de profundidad de los anillos
+const OVERLAY_DEPTH_BIAS: DepthBias = DepthBias { constant: 1e-6, slope_scale: 1.0 };
+// Radio de la capa de nubes respecto al del planeta
+const CLOUD_SHELL_SCALE: f32 = 1.03;
+// Segmentos con los que se aproxima cada órbita y su color
+const ORBIT_SEGMENTS: usize = 128;
+const ORBIT_COLOR: u32 = 0x3A3A5C;
+// Textura opcional de la Tierra (no se incluye en el repositorio)
+const EARTH_TEXTURE_PATH: &str = "assets/textures/earth.png";
+// Rango del multiplicador de velocidad de la simulación y cuánto cambia por pulsación
+const MIN_SPEED: f32 = 0.1;
+const MAX_SPEED: f32 = 10.0;
+const SPEED_STEP: f32 = 1.25;
+// Exposición inicial (la única en modo headless), su rango y cuánto se
+// multiplica por pulsación (un cuarto de paso)
+const DEFAULT_EXPOSURE: f32 = 1.0;
+const MIN_EXPOSURE: f32 = 0.25;
+const MAX_EXPOSURE: f32 = 4.0;
+const EXPOSURE_STEP: f32 = 1.189_207_1;
+// Teclas para seguir a los cuerpos de la escena, por índice
+const FOLLOW_KEYS: [Key; 9] = [
+    Key::Key1, Key::Key2, Key::Key3, Key::Key4, Key::Key5,
+    Key::Key6, Key::Key7, Key::Key8, Key::Key9,
+];
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ProjectionMode {
+    Perspective,
+    Orthographic,
+}
 
-pub struct Uniforms {
-    model_matrix: Mat4,
-    view_matrix: Mat4,
-    projection_matrix: Mat4,
-    viewport_matrix: Mat4,
-    time: u32,
-    noise: FastNoiseLite
+// Filtro de "foto antigua" aplicado al frame terminado (tecla G)
+#[derive(Clone, Copy, PartialEq)]
+pub enum PhotoFilter {
+    None,
+    Grayscale,
+    Sepia,
 }
 
-pub struct CelestialBody {
-    position: Vec3,
-    scale: f32,
-    rotation: Vec3,
-    shader_type: PlanetType,
+// Campo de visión vertical (en grados) y planos de recorte de la proyección
+#[derive(Clone, Copy)]
+pub struct ProjectionSettings {
+    fov: f32,
+    near: f32,
+    far: f32,
 }
 
-fn create_noise() -> FastNoiseLite {
-    create_cloud_noise() 
-    // create_cell_noise()
-    // create_ground_noise()
-    // create_lava_noise()
+impl ProjectionSettings {
+    pub fn new(fov: f32, near: f32, far: f32) -> Result<Self, String> {
+        if !(MIN_FOV..=MAX_FOV).contains(&fov) {
+            return Err(format!("fov must be between {} and {} degrees, got {}", MIN_FOV, MAX_FOV, fov));
+        }
+        if near <= 0.0 || near >= far {
+            return Err(format!("expected 0 < near < far, got near {} and far {}", near, far));
+        }
+        Ok(ProjectionSettings { fov, near, far })
+    }
 }
 
-fn create_cloud_noise() -> FastNoiseLite {
-    let mut noise = FastNoiseLite::with_seed(1337);
-    noise.set_noise_type(Some(NoiseType::OpenSimplex2));
-    noise
+pub struct MouseState {
+    last_position: Option<(f32, f32)>,
+    // Estado del botón izquierdo en el frame anterior, para detectar el clic
+    left_was_down: bool,
+    // Invertir el eje vertical del ratón y de las flechas (tecla I); dura toda la sesión
+    invert_pitch: bool,
 }
 
-fn create_cell_noise() -> FastNoiseLite {
-    let mut noise = FastNoiseLite::with_seed(1337);
-    noise.set_noise_type(Some(NoiseType::Cellular));
-    noise.set_frequency(Some(0.1));
-    noise
+fn create_projection_matrix(mode: ProjectionMode, settings: &ProjectionSettings, window_width: f32, window_height: f32) -> Mat4 {
+    match mode {
+        ProjectionMode::Perspective => transform::perspective(settings.fov, window_width, window_height, settings.near, settings.far),
+        ProjectionMode::Orthographic => transform::orthographic(window_width, window_height, settings.near, settings.far),
+    }
 }
 
-fn create_ground_noise() -> FastNoiseLite {
-    let mut noise = FastNoiseLite::with_seed(1337);
-    
-    // Use FBm fractal type to layer multiple octaves of noise
-    noise.set_noise_type(Some(NoiseType::Cellular)); // Cellular noise for cracks
-    noise.set_fractal_type(Some(FractalType::FBm));  // Fractal Brownian Motion
-    noise.set_fractal_octaves(Some(5));              // More octaves = more detail
-    noise.set_fractal_lacunarity(Some(2.0));         // Lacunarity controls frequency scaling
-    noise.set_fractal_gain(Some(0.5));               // Gain controls amplitude scaling
-    noise.set_frequency(Some(0.05));                 // Lower frequency for larger features
-
-    noise
+// Líneas de texto en la esquina superior izquierda, con una sombra de un
+// píxel para que se lean sobre cualquier fondo
+fn draw_hud(framebuffer: &mut Framebuffer, lines: &[String]) {
+    let scale = framebuffer.supersample_factor as i32;
+    let text = lines.join("\n");
+    framebuffer.draw_text(9 * scale, 9 * scale, &text, Color::black());
+    framebuffer.draw_text(8 * scale, 8 * scale, &text, Color::new(255, 255, 255));
 }
 
-fn create_lava_noise() -> FastNoiseLite {
-    let mut noise = FastNoiseLite::with_seed(42);
-    
-    // Use FBm for multi-layered noise, giving a "turbulent" feel
-    noise.set_noise_type(Some(NoiseType::Perlin));  // Perlin noise for smooth, natural texture
-    noise.set_fractal_type(Some(FractalType::FBm)); // FBm for layered detail
-    noise.set_fractal_octaves(Some(6));             // High octaves for rich detail
-    noise.set_fractal_lacunarity(Some(2.0));        // Higher lacunarity = more contrast between layers
-    noise.set_fractal_gain(Some(0.5));              // Higher gain = more influence of smaller details
-    noise.set_frequency(Some(0.002));                // Low frequency = large features
-    
-    noise
+// Si el archivo no existe se usa el shader procedural sin avisar; si existe
+// pero no se puede leer, se avisa y también se usa el procedural
+fn load_earth_texture() -> Option<Texture> {
+    if !std::path::Path::new(EARTH_TEXTURE_PATH).exists() {
+        return None;
+    }
+    Texture::load(EARTH_TEXTURE_PATH)
+        .map_err(|e| eprintln!("Failed to load {}: {}", EARTH_TEXTURE_PATH, e))
+        .ok()
 }
 
-fn create_model_matrix(translation: Vec3, scale: f32, rotation: Vec3) -> Mat4 {
-    let (sin_x, cos_x) = rotation.x.sin_cos();
-    let (sin_y, cos_y) = rotation.y.sin_cos();
-    let (sin_z, cos_z) = rotation.z.sin_cos();
+fn follow_tour(camera: &mut Camera, tour: &Tour, celestial_bodies: &[CelestialBody], sim_time: f32) {
+    let positions: Vec<Vec3> = celestial_bodies.iter().map(|body| body.position).collect();
+    // El recorrido ya interpola con suavidad; la cámara se coloca directo
+    let preset = tour.sample(sim_time, &positions);
+    camera.look_at(preset.eye, preset.center, preset.up);
+}
 
-    let rotation_matrix_x = Mat4::new(
-        1.0,  0.0,    0.0,   0.0,
-        0.0,  cos_x, -sin_x, 0.0,
-        0.0,  sin_x,  cos_x, 0.0,
-        0.0,  0.0,    0.0,   1.0,
-    );
+// Framebuffer interno para una ventana dada: `render_scale` reduce la
+// resolución y SSAA_FACTOR la multiplica
+fn create_framebuffer(window_width: usize, window_height: usize, render_scale: f32) -> Framebuffer {
+    let width = ((window_width as f32 * render_scale).round() as usize).max(1);
+    let height = ((window_height as f32 * render_scale).round() as usize).max(1);
+    let mut framebuffer = Framebuffer::new_supersampled(width, height, SSAA_FACTOR);
+    framebuffer.set_background_color(0x333355);
+    framebuffer
+}
 
-    let rotation_matrix_y = Mat4::new(
-        cos_y,  0.0,  sin_y, 0.0,
-        0.0,    1.0,  0.0,   0.0,
-        -sin_y, 0.0,  cos_y, 0.0,
-        0.0,    0.0,  0.0,   1.0,
-    );
+// Un único cuerpo en el origen, sin órbita, para iterar sobre un shader
+fn preview_body(shader_type: PlanetType) -> CelestialBody {
+    CelestialBody {
+        position: Vec3::new(0.0, 0.0, 0.0),
+        local_position: Vec3::new(0.0, 0.0, 0.0),
+        parent: None,
+        scale: 1.0,
+        rotation: Vec3::new(0.0, 0.0, 0.0),
+        shader_type,
+        orbit_radius: 0.0,
+        orbit_speed: 0.0,
+        orbit_angle: 0.0,
+        rotation_speed: 0.3,
+        axial_tilt: 0.0,
+        seed: 0,
+    }
+}
 
-    let rotation_matrix_z = Mat4::new(
-        cos_z, -sin_z, 0.0, 0.0,
-        sin_z,  cos_z, 0.0, 0.0,
-        0.0,    0.0,  1.0, 0.0,
-        0.0,    0.0,  0.0, 1.0,
-    );
+// Opciones de línea de comandos, p. ej. `--headless --frames 120 --out frames/`
+struct Args {
+    headless: bool,
+    frames: usize,
+    out_dir: String,
+    // Guardar los frames headless como PPM en lugar de PNG
+    ppm: bool,
+    // Dibujar el HUD de depuración en los frames headless
+    hud: bool,
+    model: String,
+    projection: ProjectionSettings,
+    // Con `--shader` se muestra un solo cuerpo en el origen en lugar de la escena
+    shader: Option<PlanetType>,
+    // Recorrido automático de la cámara; ignora los controles de cámara
+    demo: bool,
+    // Un solo cuerpo cuyo tipo se cambia con [ y ] (empieza en `--shader` o en el sol)
+    shader_gallery: bool,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args {
+        headless: false,
+        frames: 120,
+        out_dir: String::from("frames"),
+        ppm: false,
+        hud: false,
+        model: String::from("assets/models/smooth_sphere.obj"),
+        shader: None,
+        demo: false,
+        shader_gallery: false,
+        projection: ProjectionSettings { fov: DEFAULT_FOV, near: 0.1, far: 1000.0 },
+    };
+    let (mut fov, mut near, mut far) = (args.projection.fov, args.projection.near, args.projection.far);
+    let mut iter = std::env::args().skip(1);
+
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--headless" => args.headless = true,
+            "--ppm" => args.ppm = true,
+            "--hud" => args.hud = true,
+            "--demo" => args.demo = true,
+            "--shader-gallery" => args.shader_gallery = true,
+            "--frames" => {
+                let value = iter.next().ok_or("--frames requires a value")?;
+                args.frames = value.parse().map_err(|_| format!("invalid frame count '{}'", value))?;
+            }
+            "--out" => args.out_dir = iter.next().ok_or("--out requires a directory")?,
+            "--fov" => fov = parse_float(iter.next(), "--fov")?,
+            "--near" => near = parse_float(iter.next(), "--near")?,
+            "--far" => far = parse_float(iter.next(), "--far")?,
+            "--model" => args.model = iter.next().ok_or("--model requires a path")?,
+            "--shader" => {
+                let value = iter.next().ok_or("--shader requires a planet type")?;
+                args.shader = Some(value.parse()?);
+            }
+            other => return Err(format!("unknown argument '{}'", other)),
+        }
+    }
 
-    let rotation_matrix = rotation_matrix_z * rotation_matrix_y * rotation_matrix_x;
+    args.projection = ProjectionSettings::new(fov, near, far)?;
+    Ok(args)
+}
 
-    let transform_matrix = Mat4::new(
-        scale, 0.0,   0.0,   translation.x,
-        0.0,   scale, 0.0,   translation.y,
-        0.0,   0.0,   scale, translation.z,
-        0.0,   0.0,   0.0,   1.0,
-    );
+fn parse_float(value: Option<String>, flag: &str) -> Result<f32, String> {
+    let value = value.ok_or(format!("{} requires a value", flag))?;
+    value.parse().map_err(|_| format!("invalid number '{}' for {}", value, flag))
+}
 
-    transform_matrix * rotation_matrix
+// Avanza cada cuerpo en su órbita alrededor del padre (o del origen) y
+// recalcula las posiciones en el mundo
+fn update_orbits(celestial_bodies: &mut [CelestialBody], delta_time: f32) {
+    for body in celestial_bodies.iter_mut() {
+        if body.orbit_radius > 0.0 {
+            body.orbit_angle += body.orbit_speed * delta_time;
+            body.local_position = Vec3::new(
+                body.orbit_radius * body.orbit_angle.cos(),
+                body.local_position.y,
+                body.orbit_radius * body.orbit_angle.sin()
+            );
+        }
+    }
+    update_world_positions(celestial_bodies);
 }
 
+// La matriz de mundo de cada cuerpo es la de su padre por su traslación
+// local. Solo se hereda la traslación: el giro y la escala del padre se
+// aplican en transform::model y no arrastran a los hijos. La escena ya
+// viene validada sin ciclos (ver scene::load_scene).
+fn update_world_positions(celestial_bodies: &mut [CelestialBody]) {
+    let mut frames: Vec<Option<Mat4>> = vec![None; celestial_bodies.len()];
+    for index in 0..celestial_bodies.len() {
+        world_frame(celestial_bodies, &mut frames, index);
+    }
+    for (body, frame) in celestial_bodies.iter_mut().zip(frames) {
+        let origin = frame.unwrap_or_else(Mat4::identity) * Vec4::new(0.0, 0.0, 0.0, 1.0);
+        body.position = origin.xyz();
+    }
+}
 
-fn create_view_matrix(eye: Vec3, center: Vec3, up: Vec3) -> Mat4 {
-    look_at(&eye, &center, &up)
+fn world_frame(celestial_bodies: &[CelestialBody], frames: &mut [Option<Mat4>], index: usize) -> Mat4 {
+    if let Some(frame) = frames[index] {
+        return frame;
+    }
+    let body = &celestial_bodies[index];
+    let local = translation(&body.local_position);
+    let frame = match body.parent {
+        Some(parent) => world_frame(celestial_bodies, frames, parent) * local,
+        None => local,
+    };
+    frames[index] = Some(frame);
+    frame
 }
 
-fn create_perspective_matrix(window_width: f32, window_height: f32) -> Mat4 {
-    let fov = 45.0 * PI / 180.0;
-    let aspect_ratio = window_width / window_height;
-    let near = 0.1;
-    let far = 1000.0;
+// Dibuja la órbita de cada cuerpo como un polígono en el plano XZ alrededor
+// de su padre, con profundidad para que los cuerpos la tapen
+fn draw_orbits(framebuffer: &mut Framebuffer, uniforms: &Uniforms, celestial_bodies: &[CelestialBody]) {
+    let view_projection = uniforms.projection_matrix * uniforms.view_matrix;
+    // Devuelve None para puntos detrás de la cámara; los segmentos que los
+    // tocan se omiten en lugar de recortarse
+    let to_screen = |point: Vec3| {
+        let clip = view_projection * Vec4::new(point.x, point.y, point.z, 1.0);
+        if clip.w <= 0.0 {
+            return None;
+        }
+        let screen = uniforms.viewport_matrix * Vec4::new(clip.x / clip.w, clip.y / clip.w, clip.z / clip.w, 1.0);
+        Some((screen.x.round() as i32, screen.y.round() as i32, screen.z))
+    };
 
-    perspective(fov, aspect_ratio, near, far)
+    framebuffer.set_current_color(ORBIT_COLOR);
+    for body in celestial_bodies {
+        if body.orbit_radius <= 0.0 {
+            continue;
+        }
+        // Posición del padre en el mundo (los marcos solo trasladan)
+        let center = body.position - body.local_position;
+        let orbit_point = |i: usize| {
+            let angle = i as f32 / ORBIT_SEGMENTS as f32 * 2.0 * PI;
+            center + Vec3::new(body.orbit_radius * angle.cos(), body.local_position.y, body.orbit_radius * angle.sin())
+        };
+        for i in 0..ORBIT_SEGMENTS {
+            if let (Some(start), Some(end)) = (to_screen(orbit_point(i)), to_screen(orbit_point(i + 1))) {
+                framebuffer.line_with_depth(start, end);
+            }
+        }
+    }
 }
 
-fn create_viewport_matrix(width: f32, height: f32) -> Mat4 {
-    Mat4::new(
-        width / 2.0, 0.0, 0.0, width / 2.0,
-        0.0, -height / 2.0, 0.0, height / 2.0,
-        0.0, 0.0, 1.0, 0.0,
-        0.0, 0.0, 0.0, 1.0
-    )
+// Cuerpos que llevan una capa de nubes encima
+fn has_cloud_shell(planet_type: &PlanetType) -> bool {
+    matches!(planet_type, PlanetType::Earth | PlanetType::CloudPlanet)
 }
 
-fn render(framebuffer: &mut Framebuffer, uniforms: &Uniforms, vertex_array: &[Vertex], planet_type: &PlanetType) {
-    // Vertex Shader Stage
-    let mut transformed_vertices = Vec::with_capacity(vertex_array.len());
-    for vertex in vertex_array {
-        let transformed = vertex_shader(vertex, uniforms);
-        transformed_vertices.push(transformed);
-    }
-
-    // Primitive Assembly Stage
-    let mut triangles = Vec::new();
-    for i in (0..transformed_vertices.len()).step_by(3) {
-        if i + 2 < transformed_vertices.len() {
-            triangles.push([
-                transformed_vertices[i].clone(),
-                transformed_vertices[i + 1].clone(),
-                transformed_vertices[i + 2].clone(),
-            ]);
+// Mallas que comparten todos los cuerpos
+struct SceneMeshes {
+    sphere: Vec<Vertex>,
+    ring: Vec<Vertex>,
+}
+
+// Dibuja un frame completo (fondo, cuerpos y anillos) sin depender de la
+// ventana, para poder usarlo también en modo headless
+fn render_scene(
+    framebuffer: &mut Framebuffer,
+    uniforms: &mut Uniforms,
+    celestial_bodies: &[CelestialBody],
+    camera: &Camera,
+    meshes: &SceneMeshes,
+    scratch: &mut RenderScratch,
+    sim_time: f32,
+) {
+    let (sphere_vertices, ring_vertices) = (&meshes.sphere[..], &meshes.ring[..]);
+
+    // Fondo con degradado: más oscuro arriba para dar sensación de profundidad
+    framebuffer.clear_gradient(Color::from_hex(0x05050F), Color::from_hex(0x333355));
+    framebuffer.draw_starfield(1337, 0.002);
+
+    // El sol registra una luz en su posición
+    uniforms.lights.clear();
+    for body in celestial_bodies {
+        if matches!(body.shader_type, PlanetType::Sun) {
+            uniforms.lights.push(Light::new(body.position, Color::new(255, 245, 225), 1.0));
         }
     }
+    // Sin sol (p. ej. con `--shader`) se usa una luz fija arriba a la izquierda
+    if uniforms.lights.is_empty() {
+        uniforms.lights.push(Light::new(Vec3::new(-20.0, 10.0, 20.0), Color::new(255, 245, 225), 1.0));
+    }
 
-    // Rasterization Stage
-    let mut fragments = Vec::new();
-    for tri in &triangles {
-        fragments.extend(triangle(&tri[0], &tri[1], &tri[2]));
+    uniforms.time = (sim_time * TIME_TICKS_PER_SECOND) as u32;
+    uniforms.view_matrix = camera.view_matrix();
+    uniforms.camera_position = camera.eye;
+
+    if uniforms.show_orbits {
+        draw_orbits(framebuffer, uniforms, celestial_bodies);
     }
 
-    // Fragment Processing Stage
-    for fragment in fragments {
-        let x = fragment.position.x as usize;
-        let y = fragment.position.y as usize;
-        if x < framebuffer.width && y < framebuffer.height {
-            // Apply fragment shader
-            let shaded_color = fragment_shader(&fragment, &uniforms, planet_type);
-            let color = shaded_color.to_hex();
-            framebuffer.set_current_color(color);
-            framebuffer.point(x, y, fragment.depth);
+    // Cuerpos completamente fuera del frustum no pasan por el pipeline. El
+    // radio de la esfera envolvente sale de las mallas (los anillos son más anchos).
+    let planes = frustum_planes(&(uniforms.projection_matrix * uniforms.view_matrix));
+    let mesh_radius = |vertices: &[Vertex]| vertices.iter().map(|v| v.position.magnitude()).fold(0.0, f32::max);
+    let sphere_radius = mesh_radius(sphere_vertices);
+    let ring_radius = mesh_radius(ring_vertices);
+
+    // Renderizar cada cuerpo celeste. El giro usa el mismo sentido que las
+    // órbitas, y la inclinación del eje se aplica después del giro
+    for body in celestial_bodies {
+        let radius = if matches!(body.shader_type, PlanetType::RingedPlanet) {
+            sphere_radius.max(ring_radius)
+        } else {
+            sphere_radius
+        };
+        if !sphere_in_frustum(&planes, &body.position, radius * body.scale) {
+            continue;
+        }
+
+        uniforms.model_matrix = transform::model(
+            body.position,
+            body.scale,
+            body.rotation + Vec3::new(0.0, -body.rotation_speed * sim_time, body.axial_tilt)
+        );
+        // Construir el ruido es barato (solo guarda parámetros), así que
+        // se reemplaza por cuerpo en vez de mantener uno por planeta
+        uniforms.noise = noise_for(&body.shader_type, body.seed);
+        uniforms.seed = body.seed;
+
+        render(framebuffer, uniforms, sphere_vertices, &body.shader_type, scratch);
+
+        // Los anillos se dibujan después del planeta para que el z-buffer
+        // oculte la mitad que queda detrás de la esfera; comparten la
+        // inclinación del eje para que no se vean de canto
+        if matches!(body.shader_type, PlanetType::RingedPlanet) {
+            uniforms.model_matrix = transform::model(
+                body.position,
+                body.scale,
+                body.rotation + Vec3::new(0.0, 0.0, body.axial_tilt)
+            );
+            uniforms.noise = noise_for(&PlanetType::Ring, body.seed);
+            uniforms.depth_bias = OVERLAY_DEPTH_BIAS;
+            render(framebuffer, uniforms, ring_vertices, &PlanetType::Ring, scratch);
+            uniforms.depth_bias = DepthBias::default();
         }
     }
+
+    // Segunda pasada: las capas de nubes son una copia de la esfera escalada
+    // por CLOUD_SHELL_SCALE que gira con el planeta. Son transparentes y no
+    // escriben profundidad, así que se dibujan cuando ya están todos los
+    // cuerpos opacos; si no, un cuerpo dibujado después las taparía.
+    for body in celestial_bodies {
+        if !has_cloud_shell(&body.shader_type) {
+            continue;
+        }
+        let shell_scale = body.scale * CLOUD_SHELL_SCALE;
+        if !sphere_in_frustum(&planes, &body.position, sphere_radius * shell_scale) {
+            continue;
+        }
+
+        uniforms.model_matrix = transform::model(
+            body.position,
+            shell_scale,
+            body.rotation + Vec3::new(0.0, -body.rotation_speed * sim_time, body.axial_tilt)
+        );
+        uniforms.noise = noise_for(&PlanetType::CloudShell, body.seed);
+        uniforms.seed = body.seed;
+
+        render(framebuffer, uniforms, sphere_vertices, &PlanetType::CloudShell, scratch);
+    }
 }
 
-fn main() {
-    let window_width = 800;
-    let window_height = 600;
-    let framebuffer_width = 800;
-    let framebuffer_height = 600;
+// Posprocesado del frame que dejó `render_scene`. `exposure` multiplica los
+// colores lineales antes de la gamma; 1.0 los deja como están. `dither`
+// aplica dithering ordenado con la gamma, contra las bandas de los degradados.
+fn post_process(framebuffer: &mut Framebuffer, uniforms: &Uniforms, exposure: f32, dither: bool) {
+    // En modo de normales los colores codifican vectores; el posprocesado
+    // los alteraría
+    if uniforms.show_normals {
+        return;
+    }
 
-    let mut framebuffer = Framebuffer::new(framebuffer_width, framebuffer_height);
-    let mut window = Window::new(
-        "Rust Graphics - Renderer Example",
-        window_width,
-        window_height,
-        WindowOptions::default(),
-    )
-        .unwrap();
+    // Bloom para que el sol brille; va antes de la gamma porque trabaja
+    // sobre los colores lineales
+    // El radio es proporcional a la altura (8 px a 600 px de salida) para que
+    // la escala de resolución no cambie el aspecto del brillo
+    framebuffer.apply_bloom(0.75, (framebuffer.height / 75).max(1));
+    framebuffer.apply_vignette(0.35);
 
-    window.set_position(500, 500);
-    window.update();
+    // Corrección gamma antes de presentar el frame
+    framebuffer.apply_gamma(2.2, exposure, dither);
 
-    framebuffer.set_background_color(0x333355);
+    // Gradación final, común a todos los planetas
+    let (brightness, contrast, saturation) = COLOR_GRADE;
+    framebuffer.color_grade(brightness, contrast, saturation);
+}
+
+fn main() {
+    let args = parse_args().unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        eprintln!("usage: Shaders [--headless] [--frames N] [--out DIR] [--ppm] [--hud] [--model PATH] [--shader TYPE] [--fov DEG] [--near N] [--far N] [--demo] [--shader-gallery]");
+        std::process::exit(1);
+    });
 
-    // model position
-    let translation = Vec3::new(0.0, 0.0, 0.0);
-    let rotation = Vec3::new(0.0, 0.0, 0.0);
-    let scale = 1.0f32;
+    let mut window_width = 800;
+    let mut window_height = 600;
+
+    let mut render_scale: f32 = 1.0;
+    let mut framebuffer = create_framebuffer(window_width, window_height, render_scale);
 
     // camera parameters
     let mut camera = Camera::new(
@@ -218,145 +487,468 @@ fn main() {
         Vec3::new(0.0, 1.0, 0.0)
     );
 
-    let obj = Obj::load("assets/models/smooth_sphere.obj").expect("Failed to load obj");
-    let vertex_arrays = obj.get_vertex_array(); 
-    let mut time = 0;
+    let obj = Obj::load(&args.model, false).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    });
+    let meshes = SceneMeshes {
+        sphere: obj.get_vertex_array(),
+        ring: create_ring_mesh(1.4, 2.4, 128),
+    };
+    let mut scratch = RenderScratch::default();
+    let mut sim_time: f32 = 0.0; // Reloj de simulación en segundos
 
-    let noise = create_noise();
-    let projection_matrix = create_perspective_matrix(window_width as f32, window_height as f32);
-    let viewport_matrix = create_viewport_matrix(framebuffer_width as f32, framebuffer_height as f32);
+    let mut projection_mode = ProjectionMode::Perspective;
+    let mut projection_settings = args.projection;
+    let projection_matrix = create_projection_matrix(projection_mode, &projection_settings, window_width as f32, window_height as f32);
+    let viewport_matrix = transform::viewport(framebuffer.width as f32, framebuffer.height as f32);
     let mut uniforms = Uniforms { 
         model_matrix: Mat4::identity(), 
         view_matrix: Mat4::identity(), 
         projection_matrix, 
         viewport_matrix, 
         time: 0, 
-        noise
+        noise: create_cloud_noise(),
+        cull_backfaces: true,
+        toon_shading: false,
+        show_normals: false,
+        coverage_aa: false,
+        shading_mode: ShadingMode::Phong,
+        depth_bias: DepthBias::default(),
+        show_orbits: false,
+        seed: 0,
+        earth_texture: load_earth_texture(),
+        lights: Vec::new(),
+        camera_position: camera.eye,
     };
 
-    let mut celestial_bodies = vec![
-        CelestialBody {
-            position: Vec3::new(0.0, 0.0, 0.0),
-            scale: 2.0,
-            rotation: Vec3::new(0.0, 0.0, 0.0),
-            shader_type: PlanetType::Sun,
-        },
-        CelestialBody {
-            position: Vec3::new(-4.0, 0.0, 0.0),
-            scale: 0.3,
-            rotation: Vec3::new(0.0, 0.0, 0.0),
-            shader_type: PlanetType::Asteroid,
-        },
-        CelestialBody {
-            position: Vec3::new(6.0, 0.0, 0.0),
-            scale: 0.4,
-            rotation: Vec3::new(0.0, 0.0, 0.0),
-            shader_type: PlanetType::RockyPlanet,
-        },
-        CelestialBody {
-            position: Vec3::new(12.0, 0.0, 0.0),
-            scale: 0.6,
-            rotation: Vec3::new(0.0, 0.0, 0.0),
-            shader_type: PlanetType::Earth,
-        },
-        CelestialBody {
-            position: Vec3::new(18.0, 0.0, 0.0),
-            scale: 0.5,
-            rotation: Vec3::new(0.0, 0.0, 0.0),
-            shader_type: PlanetType::CrystalPlanet,
-        },
-        CelestialBody {
-            position: Vec3::new(24.0, 0.0, 0.0),
-            scale: 0.7,
-            rotation: Vec3::new(0.0, 0.0, 0.0),
-            shader_type: PlanetType::FirePlanet,
-        },
-        CelestialBody {
-            position: Vec3::new(30.0, 0.0, 0.0),
-            scale: 1.0,
-            rotation: Vec3::new(0.0, 0.0, 0.0),
-            shader_type: PlanetType::WaterPlanet,
-        },
-        CelestialBody {
-            position: Vec3::new(36.0, 0.0, 0.0),
-            scale: 0.8,
-            rotation: Vec3::new(0.0, 0.0, 0.0),
-            shader_type: PlanetType::CloudPlanet,
-        },
-        CelestialBody {
-            position: Vec3::new(12.0, 0.0, 2.0),
-            scale: 0.2,
-            rotation: Vec3::new(0.0, 0.0, 0.0),
-            shader_type: PlanetType::Moon,
+    let mut celestial_bodies = match args.shader {
+        Some(shader_type) => vec![preview_body(shader_type)],
+        None if args.shader_gallery => vec![preview_body(PlanetType::ALL[0])],
+        None => scene::load_scene("assets/scene.toml").unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }),
+    };
+    update_world_positions(&mut celestial_bodies);
+    let tour = args.demo.then(|| Tour::visiting(&celestial_bodies));
+
+    // Modo headless: renderiza un número fijo de frames a disco sin abrir
+    // ventana, avanzando la simulación a 60 FPS exactos para que sea reproducible
+    if args.headless {
+        if let Err(e) = fs::create_dir_all(&args.out_dir) {
+            eprintln!("Failed to create output directory {}: {}", args.out_dir, e);
+            std::process::exit(1);
+        }
+
+        let delta_time = 1.0 / TIME_TICKS_PER_SECOND;
+        // Solo se mide el render y el posprocesado, no la escritura a disco
+        let mut render_time = Duration::ZERO;
+        for frame in 0..args.frames {
+            update_orbits(&mut celestial_bodies, delta_time);
+            if let Some(tour) = &tour {
+                follow_tour(&mut camera, tour, &celestial_bodies, sim_time);
+                camera.update(delta_time);
+            }
+            let started = Instant::now();
+            render_scene(&mut framebuffer, &mut uniforms, &celestial_bodies, &camera, &meshes, &mut scratch, sim_time);
+            post_process(&mut framebuffer, &uniforms, DEFAULT_EXPOSURE, false);
+            render_time += started.elapsed();
+            if args.hud {
+                draw_hud(&mut framebuffer, &[
+                    format!("FRAME {}", frame),
+                    format!("TIME {:.2} S", sim_time),
+                    format!("CAM {:.1} {:.1} {:.1}", camera.eye.x, camera.eye.y, camera.eye.z),
+                ]);
+            }
+            sim_time += delta_time;
+
+            let extension = if args.ppm { "ppm" } else { "png" };
+            let path = format!("{}/frame_{:04}.{}", args.out_dir.trim_end_matches('/'), frame, extension);
+            let saved = if args.ppm { framebuffer.save_ppm(&path) } else { framebuffer.save_png(&path) };
+            if let Err(e) = saved {
+                eprintln!("Failed to save frame {}: {}", path, e);
+                std::process::exit(1);
+            }
+        }
+        println!("Rendered {} frames to {}", args.frames, args.out_dir);
+        if args.frames > 0 {
+            println!(
+                "Render time: {:.2} ms/frame on {} threads",
+                render_time.as_secs_f64() * 1000.0 / args.frames as f64,
+                rayon::current_num_threads(),
+            );
+        }
+        return;
+    }
+
+    let mut window = Window::new(
+        "Rust Graphics - Renderer Example",
+        window_width,
+        window_height,
+        WindowOptions {
+            resize: true,
+            ..WindowOptions::default()
         },
-    ];
+    )
+        .unwrap();
 
-    let moon_orbit_radius = 2.0; // Radio de la órbita de la luna
-    let moon_orbit_speed = 0.05; // Velocidad de la órbita de la luna
-    let mut moon_angle: f32 = 0.0; // Ángulo inicial de la luna
+    window.set_position(500, 500);
+    window.update();
+
+    let mut mouse_state = MouseState { last_position: None, left_was_down: false, invert_pitch: false };
+    // Radio de la malla de esfera, para las esferas envolventes del picking
+    let sphere_radius = meshes.sphere.iter().map(|v| v.position.magnitude()).fold(0.0, f32::max);
+    let mut show_depth = false;
+    let mut paused = false;
+    let mut speed_multiplier: f32 = 1.0;
+    let camera_presets = CameraPreset::defaults();
+    // Índice del cuerpo que la cámara sigue (teclas 1-9, 0 para soltarlo)
+    let mut follow_target: Option<usize> = None;
+    let mut current_preset = 0;
+
+    let mut show_hud = true;
+    let mut photo_filter = PhotoFilter::None;
+    let mut exposure = DEFAULT_EXPOSURE;
+    let mut dither = false;
+    let mut auto_render_scale = false;
+    let mut scale_cooldown: f32 = 0.0;
+
+    let mut frame_timer = FrameTimer::new();
+    let mut title = String::with_capacity(64);
 
     while window.is_open() {
         if window.is_key_down(Key::Escape) {
             break;
         }
 
-        time += 1;
+        let frame_time = frame_timer.tick().min(MAX_DELTA_TIME);
+
+        // Si la ventana cambió de tamaño, recrear el framebuffer y las matrices
+        // que dependen de la resolución. Con altura o ancho 0 (minimizada) se
+        // conserva el tamaño anterior.
+        let (new_width, new_height) = window.get_size();
+        let resized = new_width > 0 && new_height > 0 && (new_width, new_height) != (window_width, window_height);
+        if resized {
+            window_width = new_width;
+            window_height = new_height;
+            uniforms.projection_matrix = create_projection_matrix(projection_mode, &projection_settings, window_width as f32, window_height as f32);
+        }
 
-        handle_input(&window, &mut camera);
+        // Campo de visión: , lo estrecha y . lo abre
+        let fov_delta = if window.is_key_pressed(Key::Comma, KeyRepeat::Yes) {
+            -FOV_STEP
+        } else if window.is_key_pressed(Key::Period, KeyRepeat::Yes) {
+            FOV_STEP
+        } else {
+            0.0
+        };
+        if fov_delta != 0.0 {
+            projection_settings.fov = (projection_settings.fov + fov_delta).clamp(MIN_FOV, MAX_FOV);
+            uniforms.projection_matrix = create_projection_matrix(projection_mode, &projection_settings, window_width as f32, window_height as f32);
+        }
 
-        framebuffer.clear();
+        // Resolución interna: manual con [ y ], o automática (tecla R) según
+        // el tiempo de frame promedio. En la galería de shaders [ y ]
+        // cambian el tipo del cuerpo
+        let previous_scale = render_scale;
+        if args.shader_gallery {
+            let step = if window.is_key_pressed(Key::LeftBracket, KeyRepeat::No) {
+                PlanetType::ALL.len() - 1
+            } else if window.is_key_pressed(Key::RightBracket, KeyRepeat::No) {
+                1
+            } else {
+                0
+            };
+            let body = &mut celestial_bodies[0];
+            let current = PlanetType::ALL.iter().position(|t| *t == body.shader_type).unwrap_or(0);
+            body.shader_type = PlanetType::ALL[(current + step) % PlanetType::ALL.len()];
+        } else {
+            if window.is_key_pressed(Key::LeftBracket, KeyRepeat::No) {
+                render_scale -= RENDER_SCALE_STEP;
+            }
+            if window.is_key_pressed(Key::RightBracket, KeyRepeat::No) {
+                render_scale += RENDER_SCALE_STEP;
+            }
+        }
+        if window.is_key_pressed(Key::R, KeyRepeat::No) {
+            auto_render_scale = !auto_render_scale;
+        }
+        scale_cooldown -= frame_time;
+        if auto_render_scale && scale_cooldown <= 0.0 {
+            let average = frame_timer.average_frame_time();
+            if average > FRAME_TIME_BUDGET {
+                render_scale -= RENDER_SCALE_STEP;
+            } else if average < FRAME_TIME_BUDGET * 0.5 {
+                render_scale += RENDER_SCALE_STEP;
+            }
+        }
+        render_scale = render_scale.clamp(MIN_RENDER_SCALE, 1.0);
+        if render_scale != previous_scale {
+            scale_cooldown = AUTO_SCALE_COOLDOWN;
+        }
 
-        // Encontrar la posición de la Tierra
-        let earth_position = celestial_bodies.iter()
-            .find(|body| matches!(body.shader_type, PlanetType::Earth))
-            .map(|body| body.position)
-            .unwrap_or(Vec3::new(0.0, 0.0, 0.0));
+        // Recrear el framebuffer y la matriz de viewport, que dependen de la
+        // resolución interna; la cámara y la proyección solo usan la ventana
+        if resized || render_scale != previous_scale {
+            framebuffer = create_framebuffer(window_width, window_height, render_scale);
+            uniforms.viewport_matrix = transform::viewport(framebuffer.width as f32, framebuffer.height as f32);
+        }
 
-        // Actualizar la posición de la luna
-        moon_angle += moon_orbit_speed; // Incrementar el ángulo
-        let moon_position = earth_position + Vec3::new(
-            moon_orbit_radius * moon_angle.cos(),
-            0.0, // Mantener la luna en el mismo plano
-            moon_orbit_radius * moon_angle.sin()
-        );
+        handle_input(&window, &mut camera, &mut mouse_state, &mut paused, &mut speed_multiplier);
+
+        // Avanzar la simulación según el tiempo real transcurrido y la
+        // velocidad elegida; en pausa las órbitas y el giro se detienen pero
+        // la cámara sigue respondiendo
+        let delta_time = if paused { 0.0 } else { frame_time * speed_multiplier };
+        sim_time += delta_time;
+
+        // Alternar entre proyección en perspectiva y ortográfica (tecla O)
+        if window.is_key_pressed(Key::O, KeyRepeat::No) {
+            projection_mode = match projection_mode {
+                ProjectionMode::Perspective => ProjectionMode::Orthographic,
+                ProjectionMode::Orthographic => ProjectionMode::Perspective,
+            };
+            uniforms.projection_matrix = create_projection_matrix(projection_mode, &projection_settings, window_width as f32, window_height as f32);
+        }
 
-        // Asignar la nueva posición a la luna
-        if let Some(moon) = celestial_bodies.iter_mut()
-            .find(|body| matches!(body.shader_type, PlanetType::Moon))
-        {
-            moon.position = moon_position;
+        // Activar/desactivar el sombreado toon (tecla T)
+        if window.is_key_pressed(Key::T, KeyRepeat::No) {
+            uniforms.toon_shading = !uniforms.toon_shading;
         }
 
-        // Renderizar cada cuerpo celeste
-        for body in &celestial_bodies {
-            uniforms.model_matrix = create_model_matrix(
-                body.position,
-                body.scale,
-                body.rotation + Vec3::new(0.0, time as f32 * 0.01, 0.0)
-            );
-            uniforms.view_matrix = create_view_matrix(camera.eye, camera.center, camera.up);
-            uniforms.time = time;
-            
-            render(&mut framebuffer, &uniforms, &vertex_arrays, &body.shader_type);
+        // Dithering contra las bandas de los degradados (tecla K)
+        if window.is_key_pressed(Key::K, KeyRepeat::No) {
+            dither = !dither;
+        }
+
+        // Exposición (teclas U y J): aclara u oscurece toda la escena
+        if window.is_key_pressed(Key::U, KeyRepeat::Yes) {
+            exposure = (exposure * EXPOSURE_STEP).clamp(MIN_EXPOSURE, MAX_EXPOSURE);
+        }
+        if window.is_key_pressed(Key::J, KeyRepeat::Yes) {
+            exposure = (exposure / EXPOSURE_STEP).clamp(MIN_EXPOSURE, MAX_EXPOSURE);
+        }
+
+        // Antialiasing por cobertura en los bordes de los triángulos (tecla M)
+        if window.is_key_pressed(Key::M, KeyRepeat::No) {
+            uniforms.coverage_aa = !uniforms.coverage_aa;
+        }
+
+        // Recorrer los modos de sombreado: Phong, plano y Gouraud (tecla F)
+        if window.is_key_pressed(Key::F, KeyRepeat::No) {
+            uniforms.shading_mode = match uniforms.shading_mode {
+                ShadingMode::Phong => ShadingMode::Flat,
+                ShadingMode::Flat => ShadingMode::Gouraud,
+                ShadingMode::Gouraud => ShadingMode::Phong,
+            };
+        }
+
+        // Mostrar las órbitas de los planetas (tecla L)
+        if window.is_key_pressed(Key::L, KeyRepeat::No) {
+            uniforms.show_orbits = !uniforms.show_orbits;
+        }
+
+        // Visualizar las normales en espacio mundo (tecla N)
+        if window.is_key_pressed(Key::N, KeyRepeat::No) {
+            uniforms.show_normals = !uniforms.show_normals;
+        }
+
+        // Saltar a la siguiente vista predefinida (tecla C)
+        if window.is_key_pressed(Key::C, KeyRepeat::No) {
+            current_preset = (current_preset + 1) % camera_presets.len();
+            camera.apply_preset(&camera_presets[current_preset]);
+        }
+
+        // Seguir a un cuerpo celeste con las teclas 1-9, en el orden de la escena
+        for (index, key) in FOLLOW_KEYS.iter().enumerate() {
+            if index < celestial_bodies.len() && window.is_key_pressed(*key, KeyRepeat::No) {
+                follow_target = Some(index);
+            }
+        }
+        if window.is_key_pressed(Key::Key0, KeyRepeat::No) {
+            follow_target = None;
+        }
+
+        // Clic izquierdo: seleccionar el cuerpo bajo el cursor y seguirlo
+        let left_down = window.get_mouse_down(MouseButton::Left);
+        if left_down && !mouse_state.left_was_down {
+            if let Some((x, y)) = window.get_mouse_pos(MouseMode::Discard) {
+                let view_projection = uniforms.projection_matrix * uniforms.view_matrix;
+                let (origin, direction) = screen_ray(x, y, window_width as f32, window_height as f32, &view_projection);
+                if let Some(index) = pick_body(&origin, &direction, &celestial_bodies, sphere_radius) {
+                    println!("Selected body {}: {:?}", index, celestial_bodies[index].shader_type);
+                    follow_target = Some(index);
+                }
+            }
+        }
+        mouse_state.left_was_down = left_down;
+
+        // Mostrar el z-buffer en escala de grises (tecla Z)
+        if window.is_key_pressed(Key::Z, KeyRepeat::No) {
+            show_depth = !show_depth;
+        }
+
+        update_orbits(&mut celestial_bodies, delta_time);
+
+        // Mover el centro de la cámara con el cuerpo seguido, manteniendo la
+        // distancia al ojo; va después de las órbitas para usar la posición nueva
+        if let Some(index) = follow_target {
+            camera.follow(celestial_bodies[index].position);
+        }
+        // En el modo demo el recorrido pisa cualquier movimiento de la cámara
+        if let Some(tour) = &tour {
+            follow_tour(&mut camera, tour, &celestial_bodies, sim_time);
+        }
+        camera.update(frame_time);
+        render_scene(&mut framebuffer, &mut uniforms, &celestial_bodies, &camera, &meshes, &mut scratch, sim_time);
+        post_process(&mut framebuffer, &uniforms, exposure, dither);
+
+        if show_depth {
+            // Usar el rango de profundidades visibles para aprovechar todo el contraste
+            let (near, far) = framebuffer.depth_buffer().iter()
+                .filter(|depth| depth.is_finite())
+                .fold((f32::INFINITY, f32::NEG_INFINITY), |(near, far), &depth| (near.min(depth), far.max(depth)));
+            framebuffer.visualize_depth(near, far);
+        }
+
+        // Escala de grises o sepia sobre el frame final (tecla G)
+        if window.is_key_pressed(Key::G, KeyRepeat::No) {
+            photo_filter = match photo_filter {
+                PhotoFilter::None => PhotoFilter::Grayscale,
+                PhotoFilter::Grayscale => PhotoFilter::Sepia,
+                PhotoFilter::Sepia => PhotoFilter::None,
+            };
+        }
+        match photo_filter {
+            PhotoFilter::None => {}
+            PhotoFilter::Grayscale => framebuffer.map_colors(Color::grayscale),
+            PhotoFilter::Sepia => framebuffer.map_colors(Color::sepia),
+        }
+
+        // HUD de depuración (tecla H); va después del posprocesado para que
+        // el texto no se vea afectado por la gamma ni el bloom
+        if window.is_key_pressed(Key::H, KeyRepeat::No) {
+            show_hud = !show_hud;
+        }
+        if show_hud {
+            let selected = match follow_target {
+                Some(index) => format!("SEL {} {:?}", index, celestial_bodies[index].shader_type),
+                None => String::from("SEL NONE"),
+            };
+            draw_hud(&mut framebuffer, &[
+                format!("FPS {:.0} ({:.1} MS)", frame_timer.fps(), frame_timer.average_frame_time() * 1000.0),
+                format!("CAM {:.1} {:.1} {:.1}", camera.eye.x, camera.eye.y, camera.eye.z),
+                selected,
+                format!("EXP {:.2}", exposure),
+            ]);
+        }
+
+        // Captura de pantalla (tecla P), una por cada pulsación
+        if window.is_key_pressed(Key::P, KeyRepeat::No) {
+            save_screenshot(&framebuffer);
         }
 
         window
-            .update_with_buffer(&framebuffer.buffer, framebuffer_width, framebuffer_height)
+            .update_with_buffer(&framebuffer.present(window_width, window_height), window_width, window_height)
             .unwrap();
+
+        // FPS promedio en el título (reutiliza el mismo String cada frame)
+        title.clear();
+        let _ = write!(
+            title,
+            "Rust Graphics - Renderer Example — {:.0} FPS ({:.1} ms) — {:.2}x — {:.0}%{}",
+            frame_timer.fps(),
+            frame_timer.average_frame_time() * 1000.0,
+            speed_multiplier,
+            render_scale * 100.0,
+            if auto_render_scale { " auto" } else { "" }
+        );
+        match uniforms.shading_mode {
+            ShadingMode::Phong => {}
+            ShadingMode::Flat => title.push_str(" — flat"),
+            ShadingMode::Gouraud => title.push_str(" — Gouraud"),
+        }
+        if args.shader_gallery {
+            let _ = write!(title, " — {:?}", celestial_bodies[0].shader_type);
+        }
+        if paused {
+            title.push_str(" — PAUSED");
+        }
+        window.set_title(&title);
     }
 }
 
-fn handle_input(window: &Window, camera: &mut Camera) {
+// El nombre lleva la hora del sistema en milisegundos y no el tiempo de la
+// simulación, que no avanza en pausa y hacía que cada captura pisara la anterior
+fn save_screenshot(framebuffer: &Framebuffer) {
+    if let Err(e) = fs::create_dir_all("screenshots") {
+        eprintln!("Failed to create screenshots directory: {}", e);
+        return;
+    }
+
+    let millis = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_millis());
+    let path = format!("screenshots/frame_{}.png", millis);
+    match framebuffer.save_png(&path) {
+        Ok(()) => println!("Saved screenshot to {}", path),
+        Err(e) => eprintln!("Failed to save screenshot {}: {}", path, e),
+    }
+}
+
+fn handle_input(window: &Window, camera: &mut Camera, mouse_state: &mut MouseState, paused: &mut bool, speed_multiplier: &mut f32) {
     let movement_speed = 0.5;
     let rotation_speed = PI / 50.0;
     let zoom_speed = 1.0;
+    let mouse_sensitivity = 0.005;
+
+    // Pausar/reanudar la simulación (barra espaciadora)
+    if window.is_key_pressed(Key::Space, KeyRepeat::No) {
+        *paused = !*paused;
+    }
+
+    // Velocidad de la simulación (+ más rápido, - más lento)
+    if window.is_key_pressed(Key::Equal, KeyRepeat::Yes) || window.is_key_pressed(Key::NumPadPlus, KeyRepeat::Yes) {
+        *speed_multiplier = (*speed_multiplier * SPEED_STEP).clamp(MIN_SPEED, MAX_SPEED);
+    }
+    if window.is_key_pressed(Key::Minus, KeyRepeat::Yes) || window.is_key_pressed(Key::NumPadMinus, KeyRepeat::Yes) {
+        *speed_multiplier = (*speed_multiplier / SPEED_STEP).clamp(MIN_SPEED, MAX_SPEED);
+    }
+
+    // Invertir la inclinación (tecla I)
+    if window.is_key_pressed(Key::I, KeyRepeat::No) {
+        mouse_state.invert_pitch = !mouse_state.invert_pitch;
+    }
+    let pitch_sign = if mouse_state.invert_pitch { -1.0 } else { 1.0 };
+
+    // Mirar con el ratón mientras se mantiene presionado el botón derecho
+    if window.get_mouse_down(MouseButton::Right) {
+        if let Some((x, y)) = window.get_mouse_pos(MouseMode::Pass) {
+            // El primer frame solo guarda la posición para evitar un salto
+            if let Some((last_x, last_y)) = mouse_state.last_position {
+                let delta_yaw = (x - last_x) * mouse_sensitivity;
+                let delta_pitch = (y - last_y) * mouse_sensitivity * pitch_sign;
+                camera.orbit(delta_yaw, delta_pitch);
+            }
+            mouse_state.last_position = Some((x, y));
+        }
+    } else {
+        mouse_state.last_position = None;
+    }
 
     // Rotación de la cámara (mirando arriba/abajo)
     if window.is_key_down(Key::Up) {
-        camera.rotate_pitch(-rotation_speed);
+        camera.rotate_pitch(-rotation_speed * pitch_sign);
     }
     if window.is_key_down(Key::Down) {
-        camera.rotate_pitch(rotation_speed);
+        camera.rotate_pitch(rotation_speed * pitch_sign);
+    }
+
+    // Rotación de la cámara (mirando izquierda/derecha)
+    if window.is_key_down(Key::Left) {
+        camera.rotate_yaw(rotation_speed);
+    }
+    if window.is_key_down(Key::Right) {
+        camera.rotate_yaw(-rotation_speed);
     }
 
     // Movimiento WASD (adelante, izquierda, atrás, derecha)
@@ -374,12 +966,11 @@ fn handle_input(window: &Window, camera: &mut Camera) {
         movement.x += movement_speed; // Mover a la derecha
     }
 
-    // Aplicar movimiento solo si hay entrada
-    if movement.magnitude() > 0.0 {
-        camera.move_center(movement);
-    }
+    // El movimiento se pasa al marco de la cámara para que adelante sea
+    // siempre hacia dentro de la pantalla
+    camera.move_local(movement);
 
-    // Movimiento vertical (Q para subir, E para bajar)
+    // Movimiento vertical en el eje Y del mundo (Q para subir, E para bajar)
     if window.is_key_down(Key::Q) {
         camera.move_up(movement_speed);
     }
@@ -387,11 +978,16 @@ fn handle_input(window: &Window, camera: &mut Camera) {
         camera.move_up(-movement_speed);
     }
 
-    // Zoom (1 para acercar, 2 para alejar)
-    if window.is_key_down(Key::Key1) {
+    // Zoom con la rueda del ratón o Re Pág / Av Pág
+    if let Some((_, scroll)) = window.get_scroll_wheel() {
+        if scroll != 0.0 {
+            camera.zoom(scroll.signum() * zoom_speed);
+        }
+    }
+    if window.is_key_down(Key::PageUp) {
         camera.zoom(zoom_speed);
     }
-    if window.is_key_down(Key::Key2) {
+    if window.is_key_down(Key::PageDown) {
         camera.zoom(-zoom_speed);
     }
 
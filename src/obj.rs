@@ -1,6 +1,34 @@
 use tobj;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::collections::HashMap;
 use nalgebra_glm::{Vec2, Vec3};
 use crate::vertex::Vertex;
+use crate::color::Color;
+
+#[derive(Debug)]
+pub enum ObjError {
+    Io(io::Error),
+    MalformedFace { line_number: usize, line: String },
+    IndexOutOfRange { line_number: usize, line: String, index: i64 },
+    Load(tobj::LoadError),
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ObjError::Io(e) => write!(f, "failed to read OBJ file: {}", e),
+            ObjError::MalformedFace { line_number, line } => {
+                write!(f, "malformed face on line {}: '{}'", line_number, line)
+            }
+            ObjError::IndexOutOfRange { line_number, line, index } => {
+                write!(f, "index {} out of range on line {}: '{}'", index, line_number, line)
+            }
+            ObjError::Load(e) => write!(f, "failed to load OBJ file: {}", e),
+        }
+    }
+}
 
 pub struct Obj {
     meshes: Vec<Mesh>,
@@ -10,30 +38,63 @@ struct Mesh {
     vertices: Vec<Vec3>,
     normals: Vec<Vec3>,
     texcoords: Vec<Vec2>,
+    // Per-vertex colors from `v x y z r g b` lines; empty when the file has none
+    colors: Vec<Color>,
     indices: Vec<u32>,
+    material_color: Color,
 }
 
 impl Obj {
-    pub fn load(filename: &str) -> Result<Self, tobj::LoadError> {
-        let (models, _) = tobj::load_obj(filename, &tobj::LoadOptions {
+    // With `flat_shading` every triangle uses its face normal, ignoring any
+    // `vn` data; otherwise missing normals are smoothed across shared vertices
+    pub fn load(filename: &str, flat_shading: bool) -> Result<Self, ObjError> {
+        let source = fs::read_to_string(filename).map_err(ObjError::Io)?;
+        validate_faces(&source)?;
+
+        let (models, materials) = tobj::load_obj(filename, &tobj::LoadOptions {
             single_index: true,
+            // Quads and n-gons are split as a fan (v0, vi, vi+1), which keeps
+            // the face's winding; each corner carries its own UV/normal index
             triangulate: true,
             ..Default::default()
-        })?;
+        }).map_err(ObjError::Load)?;
+
+        // A missing or broken .mtl isn't fatal: meshes just keep a white base color
+        let materials = materials.unwrap_or_default();
 
         let meshes = models.into_iter().map(|model| {
             let mesh = model.mesh;
-            Mesh {
-                vertices: mesh.positions.chunks(3)
-                    .map(|v| Vec3::new(v[0], v[1], v[2]))
-                    .collect(),
-                normals: mesh.normals.chunks(3)
+            let material_color = mesh.material_id
+                .and_then(|id| materials.get(id))
+                .and_then(|material| material.diffuse)
+                .map(|kd| Color::from_float(kd[0], kd[1], kd[2]))
+                .unwrap_or(Color::new(255, 255, 255));
+
+            let vertices: Vec<Vec3> = mesh.positions.chunks(3)
+                .map(|v| Vec3::new(v[0], v[1], v[2]))
+                .collect();
+            let normals = if flat_shading {
+                // An empty list makes get_vertex_array fall back to face normals
+                Vec::new()
+            } else if mesh.normals.is_empty() {
+                smooth_normals(&vertices, &mesh.indices)
+            } else {
+                mesh.normals.chunks(3)
                     .map(|n| Vec3::new(n[0], n[1], n[2]))
-                    .collect(),
+                    .collect()
+            };
+
+            Mesh {
+                vertices,
+                normals,
                 texcoords: mesh.texcoords.chunks(2)
                     .map(|t| Vec2::new(t[0], 1.0 - t[1]))
                     .collect(),
+                colors: mesh.vertex_color.chunks(3)
+                    .map(|c| Color::from_float(c[0], c[1], c[2]))
+                    .collect(),
                 indices: mesh.indices,
+                material_color,
             }
         }).collect();
 
@@ -44,19 +105,379 @@ impl Obj {
         let mut vertices = Vec::new();
 
         for mesh in &self.meshes {
-            for &index in &mesh.indices {
-                let position = mesh.vertices[index as usize];
-                let normal = mesh.normals.get(index as usize)
-                    .cloned()
-                    .unwrap_or(Vec3::new(0.0, 1.0, 0.0));
-                let tex_coords = mesh.texcoords.get(index as usize)
-                    .cloned()
-                    .unwrap_or(Vec2::new(0.0, 0.0));
+            let tangents = compute_tangents(mesh);
+
+            for face in mesh.indices.chunks(3) {
+                if face.len() < 3 {
+                    continue;
+                }
+
+                // Face normal used when the file doesn't provide `vn` data
+                let a = mesh.vertices[face[0] as usize];
+                let b = mesh.vertices[face[1] as usize];
+                let c = mesh.vertices[face[2] as usize];
+                let face_normal = (b - a).cross(&(c - a));
+                let face_normal = if face_normal.magnitude() > 0.0 {
+                    face_normal.normalize()
+                } else {
+                    Vec3::new(0.0, 1.0, 0.0)
+                };
 
-                vertices.push(Vertex::new(position, normal, tex_coords));
+                for &index in face {
+                    let position = mesh.vertices[index as usize];
+                    let normal = mesh.normals.get(index as usize)
+                        .cloned()
+                        .unwrap_or(face_normal);
+                    let tex_coords = mesh.texcoords.get(index as usize)
+                        .cloned()
+                        .unwrap_or(Vec2::new(0.0, 0.0));
+
+                    // Vertex color (white when absent) tinted by the material
+                    let vertex_color = mesh.colors.get(index as usize)
+                        .cloned()
+                        .unwrap_or(Color::new(255, 255, 255));
+
+                    let mut vertex = Vertex::new(position, normal, tex_coords);
+                    vertex.tangent = orthonormal_tangent(&tangents[index as usize], &normal);
+                    vertex.color = vertex_color.blend_multiply(&mesh.material_color);
+                    vertices.push(vertex);
+                }
             }
         }
 
         vertices
     }
 }
+
+// Area-weighted vertex normals: each triangle adds its unnormalized cross
+// product (length = twice its area) to its corners. Vertices are welded by
+// position first, since single-index loading splits them along UV seams and
+// those copies must still share a normal.
+fn smooth_normals(vertices: &[Vec3], indices: &[u32]) -> Vec<Vec3> {
+    let key = |v: &Vec3| [v.x.to_bits(), v.y.to_bits(), v.z.to_bits()];
+    let mut accumulated: HashMap<[u32; 3], Vec3> = HashMap::new();
+
+    for face in indices.chunks(3) {
+        if face.len() < 3 {
+            continue;
+        }
+        let a = vertices[face[0] as usize];
+        let b = vertices[face[1] as usize];
+        let c = vertices[face[2] as usize];
+        let weighted_normal = (b - a).cross(&(c - a));
+
+        for corner in [a, b, c] {
+            *accumulated.entry(key(&corner)).or_insert(Vec3::zeros()) += weighted_normal;
+        }
+    }
+
+    vertices.iter().map(|v| {
+        let normal = accumulated.get(&key(v)).cloned().unwrap_or(Vec3::zeros());
+        if normal.magnitude() > 0.0 { normal.normalize() } else { Vec3::new(0.0, 1.0, 0.0) }
+    }).collect()
+}
+
+// Per-vertex tangents (Lengyel's method): each triangle contributes the
+// object-space direction of increasing U, accumulated on the vertices it
+// shares with its neighbours. Triangles with degenerate UVs are skipped.
+fn compute_tangents(mesh: &Mesh) -> Vec<Vec3> {
+    let mut tangents = vec![Vec3::new(0.0, 0.0, 0.0); mesh.vertices.len()];
+    if mesh.texcoords.len() < mesh.vertices.len() {
+        return tangents;
+    }
+
+    for face in mesh.indices.chunks(3) {
+        if face.len() < 3 {
+            continue;
+        }
+        let (i0, i1, i2) = (face[0] as usize, face[1] as usize, face[2] as usize);
+
+        let edge1 = mesh.vertices[i1] - mesh.vertices[i0];
+        let edge2 = mesh.vertices[i2] - mesh.vertices[i0];
+        let delta_uv1 = mesh.texcoords[i1] - mesh.texcoords[i0];
+        let delta_uv2 = mesh.texcoords[i2] - mesh.texcoords[i0];
+
+        let determinant = delta_uv1.x * delta_uv2.y - delta_uv2.x * delta_uv1.y;
+        if determinant.abs() < f32::EPSILON {
+            continue;
+        }
+        let tangent = (edge1 * delta_uv2.y - edge2 * delta_uv1.y) / determinant;
+
+        for index in [i0, i1, i2] {
+            tangents[index] += tangent;
+        }
+    }
+
+    tangents
+}
+
+// Gram-Schmidt against the normal; falls back to any perpendicular direction
+// when the accumulated tangent is missing or parallel to the normal
+fn orthonormal_tangent(tangent: &Vec3, normal: &Vec3) -> Vec3 {
+    let projected = tangent - normal * normal.dot(tangent);
+    if projected.magnitude() > 1e-6 {
+        return projected.normalize();
+    }
+
+    let axis = if normal.x.abs() < 0.9 { Vec3::new(1.0, 0.0, 0.0) } else { Vec3::new(0.0, 1.0, 0.0) };
+    let fallback = axis - normal * normal.dot(&axis);
+    if fallback.magnitude() > 1e-6 { fallback.normalize() } else { axis }
+}
+
+// Checks every `f` line before handing the file to tobj so errors can point
+// at the offending line (1-based) instead of a generic parse failure
+fn validate_faces(source: &str) -> Result<(), ObjError> {
+    let (mut positions, mut texcoords, mut normals) = (0i64, 0i64, 0i64);
+
+    for (i, raw_line) in source.lines().enumerate() {
+        let line = raw_line.trim();
+        let mut tokens = line.split_whitespace();
+
+        match tokens.next() {
+            Some("v") => positions += 1,
+            Some("vt") => texcoords += 1,
+            Some("vn") => normals += 1,
+            Some("f") => {
+                let malformed = || ObjError::MalformedFace { line_number: i + 1, line: line.to_string() };
+                let corners: Vec<&str> = tokens.collect();
+                if corners.len() < 3 {
+                    return Err(malformed());
+                }
+
+                for corner in corners {
+                    // v, v/vt, v//vn o v/vt/vn
+                    let parts: Vec<&str> = corner.split('/').collect();
+                    if parts.len() > 3 || parts[0].is_empty() {
+                        return Err(malformed());
+                    }
+
+                    for (part, count) in parts.iter().zip([positions, texcoords, normals]) {
+                        if part.is_empty() {
+                            continue;
+                        }
+                        let index: i64 = part.parse().map_err(|_| malformed())?;
+                        // Los índices negativos son relativos al final de la lista
+                        let resolved = if index < 0 { count + index + 1 } else { index };
+                        if index == 0 || resolved < 1 || resolved > count {
+                            return Err(ObjError::IndexOutOfRange {
+                                line_number: i + 1,
+                                line: line.to_string(),
+                                index,
+                            });
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    // Writes `source` to a file of its own under the temp dir, since tobj
+    // only loads from a path
+    fn temp_obj(name: &str, source: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("obj_test_{}_{}.obj", std::process::id(), name));
+        fs::write(&path, source).unwrap();
+        path
+    }
+
+    fn load_source(name: &str, source: &str, flat_shading: bool) -> Result<Obj, ObjError> {
+        let path = temp_obj(name, source);
+        let result = Obj::load(path.to_str().unwrap(), flat_shading);
+        fs::remove_file(&path).unwrap();
+        result
+    }
+
+    #[test]
+    fn out_of_range_index_reports_its_line() {
+        let source = "v 0 0 0\nv 1 0 0\nv 0 1 0\n\nf 1 2 3\nf 1 2 4\n";
+        match validate_faces(source) {
+            Err(ObjError::IndexOutOfRange { line_number, index, .. }) => {
+                assert_eq!(line_number, 6);
+                assert_eq!(index, 4);
+            }
+            other => panic!("expected IndexOutOfRange, got {:?}", other),
+        }
+
+        assert!(matches!(
+            load_source("out_of_range", source, false),
+            Err(ObjError::IndexOutOfRange { line_number: 6, .. })
+        ));
+    }
+
+    #[test]
+    fn negative_and_normal_indices_are_checked() {
+        let source = "v 0 0 0\nv 1 0 0\nv 0 1 0\nvn 0 0 1\nf -3//1 -2//1 -1//1\nf 1//1 2//1 3//2\n";
+        assert!(matches!(
+            validate_faces(source),
+            Err(ObjError::IndexOutOfRange { line_number: 6, index: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn valid_file_still_loads() {
+        let obj = Obj::load("assets/models/sphere.obj", false).unwrap();
+        let vertices = obj.get_vertex_array();
+        assert!(!vertices.is_empty());
+        assert_eq!(vertices.len() % 3, 0);
+    }
+
+    #[test]
+    fn split_vertices_share_a_smooth_normal() {
+        // Two faces folded along the z axis; the fold's corners appear twice
+        // (indices 1/4 and 2/5), as they would across a UV seam
+        let vertices = vec![
+            Vec3::new(-1.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0),
+            Vec3::new(0.0, 1.0, 0.0), Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0),
+        ];
+        let indices = [0, 2, 1, 5, 4, 3];
+        let normals = smooth_normals(&vertices, &indices);
+
+        assert_eq!(normals[1], normals[4]);
+        assert_eq!(normals[2], normals[5]);
+        // Both faces contribute, so the shared normal sits between them
+        let expected = Vec3::new(1.0, 1.0, 0.0).normalize();
+        assert!((normals[1] - expected).magnitude() < 1e-6, "{:?}", normals[1]);
+        assert!((normals[0] - Vec3::new(0.0, 1.0, 0.0)).magnitude() < 1e-6);
+        assert!((normals[3] - Vec3::new(1.0, 0.0, 0.0)).magnitude() < 1e-6);
+    }
+
+    // Unit cube with one `vn` per face; shared corners need different normals
+    // on each face, which smoothing would average into a diagonal
+    const CUBE: &str = "\
+v -1 -1 -1
+v 1 -1 -1
+v 1 1 -1
+v -1 1 -1
+v -1 -1 1
+v 1 -1 1
+v 1 1 1
+v -1 1 1
+vn 0 0 -1
+vn 0 0 1
+vn -1 0 0
+vn 1 0 0
+vn 0 -1 0
+vn 0 1 0
+f 1//1 4//1 3//1 2//1
+f 5//2 6//2 7//2 8//2
+f 1//3 5//3 8//3 4//3
+f 2//4 3//4 7//4 6//4
+f 1//5 2//5 6//5 5//5
+f 4//6 8//6 7//6 3//6
+";
+
+    #[test]
+    fn cube_uses_the_normals_from_the_file() {
+        let vertices = load_source("cube", CUBE, false).unwrap().get_vertex_array();
+        assert_eq!(vertices.len(), 6 * 2 * 3);
+
+        for triangle in vertices.chunks(3) {
+            let normal = triangle[0].normal;
+            let centroid = (triangle[0].position + triangle[1].position + triangle[2].position) / 3.0;
+            // The face's axis: the centroid of a face sits on it at distance 1
+            let expected = centroid.map(|c| if c.abs() > 0.5 { c.signum() } else { 0.0 });
+            for vertex in triangle {
+                assert_eq!(vertex.normal, normal);
+                assert_eq!(vertex.normal, expected, "triangle at {:?}", centroid);
+            }
+        }
+    }
+
+    // Icosahedron subdivided once and pushed onto the unit sphere, written
+    // without `vn` lines so the loader has to compute the normals
+    fn icosphere_source() -> String {
+        let t = (1.0 + 5f32.sqrt()) / 2.0;
+        let mut positions: Vec<Vec3> = [
+            (-1.0, t, 0.0), (1.0, t, 0.0), (-1.0, -t, 0.0), (1.0, -t, 0.0),
+            (0.0, -1.0, t), (0.0, 1.0, t), (0.0, -1.0, -t), (0.0, 1.0, -t),
+            (t, 0.0, -1.0), (t, 0.0, 1.0), (-t, 0.0, -1.0), (-t, 0.0, 1.0),
+        ].iter().map(|&(x, y, z)| Vec3::new(x, y, z).normalize()).collect();
+        let faces = [
+            [0, 11, 5], [0, 5, 1], [0, 1, 7], [0, 7, 10], [0, 10, 11],
+            [1, 5, 9], [5, 11, 4], [11, 10, 2], [10, 7, 6], [7, 1, 8],
+            [3, 9, 4], [3, 4, 2], [3, 2, 6], [3, 6, 8], [3, 8, 9],
+            [4, 9, 5], [2, 4, 11], [6, 2, 10], [8, 6, 7], [9, 8, 1],
+        ];
+
+        let mut midpoints: HashMap<(usize, usize), usize> = HashMap::new();
+        let mut midpoint = |a: usize, b: usize, positions: &mut Vec<Vec3>| {
+            *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                positions.push(((positions[a] + positions[b]) / 2.0).normalize());
+                positions.len() - 1
+            })
+        };
+        let mut subdivided = Vec::new();
+        for [a, b, c] in faces {
+            let (ab, bc, ca) = (midpoint(a, b, &mut positions), midpoint(b, c, &mut positions), midpoint(c, a, &mut positions));
+            subdivided.extend([[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]);
+        }
+
+        let mut source = String::new();
+        for p in &positions {
+            source += &format!("v {} {} {}\n", p.x, p.y, p.z);
+        }
+        for [a, b, c] in subdivided {
+            source += &format!("f {} {} {}\n", a + 1, b + 1, c + 1);
+        }
+        source
+    }
+
+    #[test]
+    fn icosphere_gets_radial_smooth_normals() {
+        let source = icosphere_source();
+        let vertices = load_source("icosphere", &source, false).unwrap().get_vertex_array();
+        assert_eq!(vertices.len(), 80 * 3);
+
+        for vertex in &vertices {
+            let radial = vertex.position.normalize();
+            assert!((vertex.normal.magnitude() - 1.0).abs() < 1e-5);
+            assert!(vertex.normal.dot(&radial) > 0.99, "normal {:?} at {:?}", vertex.normal, vertex.position);
+        }
+
+        // With flat shading every corner takes its face's normal instead
+        let flat = load_source("icosphere_flat", &source, true).unwrap().get_vertex_array();
+        for triangle in flat.chunks(3) {
+            assert!(triangle.iter().all(|vertex| vertex.normal == triangle[0].normal));
+            assert!(triangle[0].normal.dot(&triangle[0].position.normalize()) < 0.99);
+        }
+    }
+
+    #[test]
+    fn quad_is_split_into_two_triangles_with_its_winding() {
+        // Counter-clockwise seen from +z, with per-corner UVs and normal
+        let source = "\
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+vt 0 0
+vt 1 0
+vt 1 1
+vt 0 1
+vn 0 0 1
+f 1/1/1 2/2/1 3/3/1 4/4/1
+";
+        let vertices = load_source("quad", source, false).unwrap().get_vertex_array();
+        assert_eq!(vertices.len(), 6);
+
+        for triangle in vertices.chunks(3) {
+            let winding = (triangle[1].position - triangle[0].position).cross(&(triangle[2].position - triangle[0].position));
+            assert!(winding.z > 0.0, "triangle flipped: {:?}", winding);
+            for vertex in triangle {
+                assert_eq!(vertex.normal, Vec3::new(0.0, 0.0, 1.0));
+                // v is flipped on load, so image rows go top to bottom
+                assert_eq!(vertex.tex_coords, Vec2::new(vertex.position.x, 1.0 - vertex.position.y));
+                // u grows with x, and flipping v leaves u alone, so the tangent is +x
+                assert!((vertex.tangent - Vec3::new(1.0, 0.0, 0.0)).norm() < 1e-5, "tangent: {:?}", vertex.tangent);
+            }
+        }
+    }
+}
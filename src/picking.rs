@@ -0,0 +1,125 @@
+use nalgebra_glm::{Vec3, Vec4, Mat4};
+use crate::CelestialBody;
+
+// Rayo en espacio del mundo que sale de la cámara y pasa por el píxel (x, y)
+// de la ventana, deshaciendo `view_projection` (projection * view)
+pub fn screen_ray(x: f32, y: f32, width: f32, height: f32, view_projection: &Mat4) -> (Vec3, Vec3) {
+    let ndc_x = 2.0 * x / width - 1.0;
+    let ndc_y = 1.0 - 2.0 * y / height;
+    let inverse = view_projection
+        .try_inverse()
+        .unwrap_or(Mat4::identity());
+
+    let unproject = |z: f32| {
+        let point = inverse * Vec4::new(ndc_x, ndc_y, z, 1.0);
+        Vec3::new(point.x, point.y, point.z) / point.w
+    };
+    let near = unproject(-1.0);
+    let far = unproject(1.0);
+
+    (near, (far - near).normalize())
+}
+
+// Índice del cuerpo más cercano cuya esfera envolvente corta el rayo
+pub fn pick_body(origin: &Vec3, direction: &Vec3, celestial_bodies: &[CelestialBody], mesh_radius: f32) -> Option<usize> {
+    let mut nearest: Option<(usize, f32)> = None;
+
+    for (index, body) in celestial_bodies.iter().enumerate() {
+        let radius = mesh_radius * body.scale;
+        let to_center = body.position - origin;
+        let along = to_center.dot(direction);
+        let distance_squared = to_center.magnitude_squared() - along * along;
+        if distance_squared > radius * radius {
+            continue;
+        }
+
+        // Primera intersección delante del origen
+        let half_chord = (radius * radius - distance_squared).sqrt();
+        let hit = if along - half_chord >= 0.0 { along - half_chord } else { along + half_chord };
+        if hit < 0.0 {
+            continue;
+        }
+
+        if nearest.is_none_or(|(_, best)| hit < best) {
+            nearest = Some((index, hit));
+        }
+    }
+
+    nearest.map(|(index, _)| index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::planet::PlanetType;
+    use crate::transform;
+
+    const WIDTH: f32 = 800.0;
+    const HEIGHT: f32 = 600.0;
+
+    fn body(position: Vec3, scale: f32) -> CelestialBody {
+        CelestialBody {
+            position,
+            local_position: position,
+            parent: None,
+            scale,
+            rotation: Vec3::new(0.0, 0.0, 0.0),
+            shader_type: PlanetType::RockyPlanet,
+            orbit_radius: 0.0,
+            orbit_speed: 0.0,
+            orbit_angle: 0.0,
+            rotation_speed: 0.0,
+            axial_tilt: 0.0,
+            seed: 0,
+        }
+    }
+
+    fn view_projection() -> Mat4 {
+        let view = transform::view(Vec3::new(4.0, 6.0, 30.0), Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        transform::perspective(60.0, WIDTH, HEIGHT, 0.1, 1000.0) * view
+    }
+
+    // Píxel de la ventana en el que se dibuja el centro de `point`
+    fn screen_position(point: &Vec3, view_projection: &Mat4) -> (f32, f32) {
+        let clip = view_projection * Vec4::new(point.x, point.y, point.z, 1.0);
+        let screen = transform::viewport(WIDTH, HEIGHT) * (clip / clip.w);
+        (screen.x, screen.y)
+    }
+
+    fn pick_at(point: &Vec3, bodies: &[CelestialBody]) -> Option<usize> {
+        let view_projection = view_projection();
+        let (x, y) = screen_position(point, &view_projection);
+        let (origin, direction) = screen_ray(x, y, WIDTH, HEIGHT, &view_projection);
+        pick_body(&origin, &direction, bodies, 1.0)
+    }
+
+    #[test]
+    fn clicking_a_body_center_picks_it() {
+        let bodies = [
+            body(Vec3::new(0.0, 0.0, 0.0), 2.0),
+            body(Vec3::new(8.0, 0.0, -3.0), 0.5),
+            body(Vec3::new(-12.0, 1.0, 4.0), 0.3),
+        ];
+        for (index, body) in bodies.iter().enumerate() {
+            assert_eq!(pick_at(&body.position, &bodies), Some(index));
+        }
+        // Lejos de todos los cuerpos no se selecciona nada
+        assert_eq!(pick_at(&Vec3::new(0.0, 12.0, 0.0), &bodies), None);
+    }
+
+    #[test]
+    fn nearest_body_along_the_ray_wins() {
+        let eye = Vec3::new(4.0, 6.0, 30.0);
+        // Ambos sobre la línea de vista hacia el origen; el segundo está delante
+        let bodies = [body(Vec3::new(0.0, 0.0, 0.0), 1.0), body(eye * 0.5, 1.0)];
+        assert_eq!(pick_at(&bodies[0].position, &bodies), Some(1));
+    }
+
+    #[test]
+    fn screen_ray_starts_on_the_near_plane_and_points_into_the_screen() {
+        let (origin, direction) = screen_ray(WIDTH / 2.0, HEIGHT / 2.0, WIDTH, HEIGHT, &view_projection());
+        let eye = Vec3::new(4.0, 6.0, 30.0);
+        assert!(((origin - eye).magnitude() - 0.1).abs() < 1e-3, "{:?}", origin);
+        assert!((direction - (-eye).normalize()).magnitude() < 1e-4, "{:?}", direction);
+    }
+}
@@ -1,3 +1,6 @@
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum PlanetType {
     Sun,
     RockyPlanet,
@@ -8,4 +11,57 @@ pub enum PlanetType {
     CloudPlanet,
     Moon,
     Asteroid,
+    RingedPlanet,
+    Ring,
+    CloudShell,
+    GasGiant,
+    IcePlanet,
+}
+
+impl PlanetType {
+    // Todos los tipos, en el orden en que los recorre `--shader-gallery`
+    pub const ALL: [PlanetType; 14] = [
+        PlanetType::Sun,
+        PlanetType::RockyPlanet,
+        PlanetType::Earth,
+        PlanetType::CrystalPlanet,
+        PlanetType::FirePlanet,
+        PlanetType::WaterPlanet,
+        PlanetType::CloudPlanet,
+        PlanetType::Moon,
+        PlanetType::Asteroid,
+        PlanetType::RingedPlanet,
+        PlanetType::Ring,
+        PlanetType::CloudShell,
+        PlanetType::GasGiant,
+        PlanetType::IcePlanet,
+    ];
+}
+
+impl FromStr for PlanetType {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_lowercase().as_str() {
+            "sun" => Ok(PlanetType::Sun),
+            "rockyplanet" => Ok(PlanetType::RockyPlanet),
+            "earth" => Ok(PlanetType::Earth),
+            "crystalplanet" => Ok(PlanetType::CrystalPlanet),
+            "fireplanet" => Ok(PlanetType::FirePlanet),
+            "waterplanet" => Ok(PlanetType::WaterPlanet),
+            "cloudplanet" => Ok(PlanetType::CloudPlanet),
+            "moon" => Ok(PlanetType::Moon),
+            "asteroid" => Ok(PlanetType::Asteroid),
+            "ringedplanet" => Ok(PlanetType::RingedPlanet),
+            "ring" => Ok(PlanetType::Ring),
+            "cloudshell" => Ok(PlanetType::CloudShell),
+            "gasgiant" => Ok(PlanetType::GasGiant),
+            "iceplanet" => Ok(PlanetType::IcePlanet),
+            _ => Err(format!(
+                "unknown planet type '{}' (expected one of: Sun, RockyPlanet, Earth, CrystalPlanet, \
+                 FirePlanet, WaterPlanet, CloudPlanet, Moon, Asteroid, RingedPlanet, Ring, CloudShell, GasGiant, IcePlanet)",
+                name
+            )),
+        }
+    }
 }
@@ -0,0 +1,83 @@
+use nalgebra_glm::{Vec2, Vec3};
+use std::f32::consts::PI;
+use crate::vertex::Vertex;
+
+// Builds a flat ring disk in the XZ plane as a triangle list ready for `render`.
+// Each quad is emitted twice with opposite winding so the ring survives
+// back-face culling from above and below. The texture coordinates carry the
+// radial position (u: 0 inner, 1 outer) and the angle (v: 0..1) for the shader.
+pub fn create_ring_mesh(inner_radius: f32, outer_radius: f32, segments: usize) -> Vec<Vertex> {
+    let mut vertices = Vec::with_capacity(segments * 12);
+    let up = Vec3::new(0.0, 1.0, 0.0);
+    let down = Vec3::new(0.0, -1.0, 0.0);
+
+    for i in 0..segments {
+        let t0 = i as f32 / segments as f32;
+        let t1 = (i + 1) as f32 / segments as f32;
+        let (sin0, cos0) = (t0 * 2.0 * PI).sin_cos();
+        let (sin1, cos1) = (t1 * 2.0 * PI).sin_cos();
+
+        let inner0 = Vec3::new(inner_radius * cos0, 0.0, inner_radius * sin0);
+        let outer0 = Vec3::new(outer_radius * cos0, 0.0, outer_radius * sin0);
+        let inner1 = Vec3::new(inner_radius * cos1, 0.0, inner_radius * sin1);
+        let outer1 = Vec3::new(outer_radius * cos1, 0.0, outer_radius * sin1);
+
+        let quad = [
+            (inner0, Vec2::new(0.0, t0)),
+            (outer0, Vec2::new(1.0, t0)),
+            (outer1, Vec2::new(1.0, t1)),
+            (inner1, Vec2::new(0.0, t1)),
+        ];
+
+        // Cara superior (antihoraria vista desde arriba)
+        for &index in &[0, 3, 2, 0, 2, 1] {
+            let (position, tex_coords) = quad[index];
+            vertices.push(Vertex::new(position, up, tex_coords));
+        }
+
+        // Cara inferior
+        for &index in &[0, 1, 2, 0, 2, 3] {
+            let (position, tex_coords) = quad[index];
+            vertices.push(Vertex::new(position, down, tex_coords));
+        }
+    }
+
+    vertices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ring_has_two_faces_per_segment_on_its_radii() {
+        let (inner, outer, segments) = (1.5, 2.5, 16);
+        let vertices = create_ring_mesh(inner, outer, segments);
+        assert_eq!(vertices.len(), segments * 12);
+
+        for vertex in &vertices {
+            let radius = Vec2::new(vertex.position.x, vertex.position.z).magnitude();
+            assert_eq!(vertex.position.y, 0.0);
+            // u is 0 on the inner edge and 1 on the outer one
+            let expected = if vertex.tex_coords.x == 0.0 { inner } else { outer };
+            assert!((radius - expected).abs() < 1e-5, "radius {} with u {}", radius, vertex.tex_coords.x);
+        }
+    }
+
+    // The winding of every triangle must agree with its normal, so culling
+    // keeps the top face from above and the bottom face from below
+    #[test]
+    fn each_face_winds_toward_its_normal() {
+        let vertices = create_ring_mesh(1.0, 2.0, 8);
+        let (mut up, mut down) = (0, 0);
+
+        for triangle in vertices.chunks(3) {
+            let normal = triangle[0].normal;
+            assert!(triangle.iter().all(|vertex| vertex.normal == normal));
+            let winding = (triangle[1].position - triangle[0].position).cross(&(triangle[2].position - triangle[0].position));
+            assert!(winding.dot(&normal) > 0.0, "triangle winds against {:?}", normal);
+            if normal.y > 0.0 { up += 1 } else { down += 1 }
+        }
+        assert_eq!((up, down), (16, 16));
+    }
+}
@@ -0,0 +1,253 @@
+use std::fmt;
+use std::fs;
+use nalgebra_glm::Vec3;
+use serde::Deserialize;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use crate::CelestialBody;
+use crate::planet::PlanetType;
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io(String, std::io::Error),
+    Parse(String, toml::de::Error),
+    UnknownShader(String, usize, String),
+    InvalidParent(String, usize, String),
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SceneError::Io(path, e) => write!(f, "failed to read scene '{}': {}", path, e),
+            SceneError::Parse(path, e) => write!(f, "failed to parse scene '{}': {}", path, e),
+            SceneError::UnknownShader(path, index, message) => {
+                write!(f, "invalid body #{} in scene '{}': {}", index, path, message)
+            }
+            SceneError::InvalidParent(path, index, message) => {
+                write!(f, "invalid parent for body #{} in scene '{}': {}", index, path, message)
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct SceneFile {
+    // Sortea una semilla para cada cuerpo que no fije la suya
+    #[serde(default)]
+    randomize_seeds: bool,
+    bodies: Vec<BodyConfig>,
+}
+
+#[derive(Deserialize)]
+struct BodyConfig {
+    position: [f32; 3],
+    scale: f32,
+    #[serde(default)]
+    rotation: [f32; 3],
+    shader_type: String,
+    #[serde(default)]
+    orbit_radius: f32,
+    #[serde(default)]
+    orbit_speed: f32,
+    #[serde(default)]
+    orbit_angle: f32,
+    #[serde(default)]
+    rotation_speed: f32,
+    #[serde(default)]
+    axial_tilt: f32,
+    #[serde(default)]
+    seed: Option<u64>,
+    #[serde(default)]
+    parent: Option<usize>,
+}
+
+pub fn load_scene(path: &str) -> Result<Vec<CelestialBody>, SceneError> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| SceneError::Io(path.to_string(), e))?;
+    parse_scene(path, &contents, rand::random())
+}
+
+// `path` solo se usa en los mensajes de error. Con randomize_seeds, las
+// semillas que faltan salen de `scene_seed`, así que la misma semilla da
+// siempre la misma escena.
+fn parse_scene(path: &str, contents: &str, scene_seed: u64) -> Result<Vec<CelestialBody>, SceneError> {
+    let scene: SceneFile = toml::from_str(contents)
+        .map_err(|e| SceneError::Parse(path.to_string(), e))?;
+
+    let parents: Vec<Option<usize>> = scene.bodies.iter().map(|body| body.parent).collect();
+    validate_parents(path, &parents)?;
+
+    let randomize_seeds = scene.randomize_seeds;
+    let mut rng = StdRng::seed_from_u64(scene_seed);
+    scene.bodies.into_iter().enumerate().map(|(index, body)| {
+        let shader_type = body.shader_type.parse::<PlanetType>()
+            .map_err(|message| SceneError::UnknownShader(path.to_string(), index, message))?;
+
+        Ok(CelestialBody {
+            // La posición en el mundo se calcula al actualizar las órbitas
+            position: Vec3::new(body.position[0], body.position[1], body.position[2]),
+            local_position: Vec3::new(body.position[0], body.position[1], body.position[2]),
+            parent: body.parent,
+            scale: body.scale,
+            rotation: Vec3::new(body.rotation[0], body.rotation[1], body.rotation[2]),
+            shader_type,
+            orbit_radius: body.orbit_radius,
+            orbit_speed: body.orbit_speed,
+            orbit_angle: body.orbit_angle,
+            rotation_speed: body.rotation_speed,
+            axial_tilt: body.axial_tilt.to_radians(),
+            seed: body.seed.unwrap_or_else(|| if randomize_seeds { rng.gen() } else { 0 }),
+        })
+    }).collect()
+}
+
+// Cada padre debe ser otro cuerpo de la escena, y subiendo de padre en padre
+// hay que llegar a una raíz: un recorrido más largo que la escena es un ciclo.
+// Todos los índices se revisan antes de recorrer, porque el recorrido sigue
+// los padres de otros cuerpos
+fn validate_parents(path: &str, parents: &[Option<usize>]) -> Result<(), SceneError> {
+    let invalid = |index: usize, message: String| {
+        SceneError::InvalidParent(path.to_string(), index, message)
+    };
+
+    for (index, parent) in parents.iter().enumerate() {
+        match *parent {
+            Some(parent) if parent >= parents.len() => {
+                return Err(invalid(index, format!("there is no body #{}", parent)));
+            }
+            Some(parent) if parent == index => {
+                return Err(invalid(index, String::from("a body can't be its own parent")));
+            }
+            _ => {}
+        }
+    }
+
+    for (index, parent) in parents.iter().enumerate() {
+        let mut current = *parent;
+        for _ in 0..parents.len() {
+            match current {
+                Some(ancestor) => current = parents[ancestor],
+                None => break,
+            }
+        }
+        if current.is_some() {
+            return Err(invalid(index, String::from("parents form a cycle")));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SUN: &str = "\
+[[bodies]]
+position = [0.0, 0.0, 0.0]
+scale = 2.0
+shader_type = \"Sun\"
+";
+
+    fn parent_error(parents: &[Option<usize>]) -> Option<usize> {
+        match validate_parents("test.toml", parents) {
+            Err(SceneError::InvalidParent(_, index, _)) => Some(index),
+            Err(other) => panic!("unexpected error: {}", other),
+            Ok(()) => None,
+        }
+    }
+
+    #[test]
+    fn valid_hierarchy_is_accepted() {
+        // Sol, Tierra alrededor del sol y luna alrededor de la Tierra
+        assert_eq!(parent_error(&[None, Some(0), Some(1)]), None);
+    }
+
+    #[test]
+    fn parent_out_of_range_is_rejected() {
+        assert_eq!(parent_error(&[None, Some(2)]), Some(1));
+    }
+
+    #[test]
+    fn ancestor_out_of_range_is_rejected() {
+        // El cuerpo 0 es válido, pero su padre apunta fuera de la escena
+        assert_eq!(parent_error(&[Some(1), Some(5)]), Some(1));
+    }
+
+    #[test]
+    fn body_cannot_be_its_own_parent() {
+        assert_eq!(parent_error(&[None, Some(1)]), Some(1));
+    }
+
+    #[test]
+    fn parent_cycle_is_rejected() {
+        assert_eq!(parent_error(&[None, Some(2), Some(3), Some(1)]), Some(1));
+    }
+
+    #[test]
+    fn minimal_scene_uses_defaults() {
+        let bodies = parse_scene("test.toml", SUN, 0).unwrap();
+        assert_eq!(bodies.len(), 1);
+        assert!(matches!(bodies[0].shader_type, PlanetType::Sun));
+        assert_eq!(bodies[0].scale, 2.0);
+        assert_eq!((bodies[0].orbit_radius, bodies[0].seed, bodies[0].parent), (0.0, 0, None));
+    }
+
+    #[test]
+    fn bundled_scene_loads() {
+        let bodies = load_scene(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/scene.toml")).unwrap();
+        assert!(!bodies.is_empty());
+    }
+
+    #[test]
+    fn malformed_toml_is_a_parse_error() {
+        let contents = SUN.replace("scale = 2.0", "scale = ");
+        assert!(matches!(parse_scene("test.toml", &contents, 0), Err(SceneError::Parse(..))));
+    }
+
+    #[test]
+    fn missing_fields_are_parse_errors() {
+        for field in ["position = [0.0, 0.0, 0.0]\n", "scale = 2.0\n", "shader_type = \"Sun\"\n"] {
+            let contents = SUN.replace(field, "");
+            assert!(matches!(parse_scene("test.toml", &contents, 0), Err(SceneError::Parse(..))), "without {}", field.trim());
+        }
+        assert!(matches!(parse_scene("test.toml", "", 0), Err(SceneError::Parse(..))));
+    }
+
+    #[test]
+    fn unknown_planet_type_names_the_body() {
+        let contents = format!("{}{}", SUN, SUN.replace("Sun", "Pluto"));
+        match parse_scene("test.toml", &contents, 0) {
+            Err(SceneError::UnknownShader(path, index, _)) => assert_eq!((path.as_str(), index), ("test.toml", 1)),
+            Err(other) => panic!("unexpected error: {}", other),
+            Ok(_) => panic!("Pluto should not be a planet type"),
+        }
+    }
+
+    #[test]
+    fn missing_file_is_an_io_error() {
+        assert!(matches!(load_scene("assets/does_not_exist.toml"), Err(SceneError::Io(..))));
+    }
+
+    fn seeds(contents: &str, scene_seed: u64) -> Vec<u64> {
+        parse_scene("test.toml", contents, scene_seed).unwrap().iter().map(|body| body.seed).collect()
+    }
+
+    #[test]
+    fn randomized_seeds_follow_the_scene_seed() {
+        // Dos cuerpos sin semilla y uno con la suya
+        let contents = format!("randomize_seeds = true\n{}{}{}seed = 42\n", SUN, SUN, SUN);
+        let first = seeds(&contents, 1);
+
+        assert_eq!(first, seeds(&contents, 1));
+        assert_ne!(first, seeds(&contents, 2));
+        assert_ne!(first[0], first[1]);
+        assert_eq!(first[2], 42);
+    }
+
+    #[test]
+    fn seeds_default_to_zero_without_randomize() {
+        let contents = format!("{}{}", SUN, SUN);
+        assert_eq!(seeds(&contents, 1), vec![0, 0]);
+        assert_eq!(seeds(&contents, 2), vec![0, 0]);
+    }
+}
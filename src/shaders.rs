@@ -1,12 +1,14 @@
-use nalgebra_glm::{Vec3, Vec4, Mat3, mat4_to_mat3};
+use nalgebra_glm::{Vec2, Vec3, Vec4, Mat3, mat4_to_mat3, dot};
 use crate::vertex::Vertex;
 use crate::Uniforms;
 use crate::fragment::Fragment;
 use crate::color::Color;
-use rand::Rng;
-use rand::SeedableRng;
-use rand::rngs::StdRng;
 use crate::PlanetType;
+use crate::light::Light;
+use crate::texture::Texture;
+use fastnoise_lite::{FastNoiseLite, NoiseType, CellularDistanceFunction, CellularReturnType};
+use once_cell::sync::Lazy;
+use std::f32::consts::PI;
 
 pub fn vertex_shader(vertex: &Vertex, uniforms: &Uniforms) -> Vertex {
   // Transform position
@@ -35,71 +37,463 @@ pub fn vertex_shader(vertex: &Vertex, uniforms: &Uniforms) -> Vertex {
   let normal_matrix = model_mat3.transpose().try_inverse().unwrap_or(Mat3::identity());
 
   let transformed_normal = normal_matrix * vertex.normal;
+  // La tangente es una dirección sobre la superficie, así que usa la matriz del modelo
+  let transformed_tangent = model_mat3 * vertex.tangent;
 
   // Create a new Vertex with transformed attributes
   Vertex {
     position: vertex.position,
     normal: vertex.normal,
     tex_coords: vertex.tex_coords,
+    tangent: vertex.tangent,
     color: vertex.color,
     transformed_position: Vec3::new(screen_position.x, screen_position.y, screen_position.z),
     transformed_normal,
+    transformed_tangent,
+    clip_position: transformed,
+    inv_w: 1.0 / w,
   }
 }
 
+// Luz mínima para que el lado oscuro no quede completamente negro
+const AMBIENT_LIGHT: f32 = 0.1;
+
+// Bandas del modo toon: (umbral de luz difusa, intensidad de la banda)
+const TOON_BANDS: [(f32, f32); 3] = [(0.75, 1.0), (0.45, 0.7), (0.2, 0.45)];
+const TOON_SHADOW: f32 = 0.2;
+// Los fragmentos casi perpendiculares a la cámara forman el contorno
+const TOON_OUTLINE_THRESHOLD: f32 = 0.25;
+
+// Cráteres de la luna y los asteroides: celdas de ruido celular por unidad del
+// objeto, radio del cráter relativo a la celda, oscurecimiento del fondo y
+// brillo del borde
+const CRATER_DENSITY: f32 = 6.0;
+const CRATER_RADIUS: f32 = 0.45;
+const CRATER_DEPTH: f32 = 0.35;
+const CRATER_RIM_BRIGHTNESS: f32 = 0.25;
+
+// Ruido celular fijo para los cráteres; devuelve la distancia al punto de celda más cercano
+static CRATER_NOISE: Lazy<FastNoiseLite> = Lazy::new(|| {
+    let mut noise = FastNoiseLite::with_seed(7);
+    noise.set_noise_type(Some(NoiseType::Cellular));
+    noise.set_cellular_distance_function(Some(CellularDistanceFunction::Euclidean));
+    noise.set_cellular_return_type(Some(CellularReturnType::Distance));
+    noise.set_frequency(Some(1.0));
+    noise
+});
+
+// Gigante gaseoso: número de bandas de polo a polo y cuánto las deforma la turbulencia
+const GAS_BAND_COUNT: f32 = 7.0;
+const GAS_TURBULENCE: f32 = 0.12;
+
+// Planeta de hielo: exponente especular (más alto = brillo más concentrado)
+// y ancho de las grietas en unidades de distancia celular
+const ICE_SHININESS: f32 = 256.0;
+const ICE_CRACK_WIDTH: f32 = 0.04;
+
+// Ruido celular para las grietas del hielo: F2 - F1 es casi cero junto a los
+// bordes entre celdas, lo que da líneas finas
+static CRACK_NOISE: Lazy<FastNoiseLite> = Lazy::new(|| {
+    let mut noise = FastNoiseLite::with_seed(99);
+    noise.set_noise_type(Some(NoiseType::Cellular));
+    noise.set_cellular_distance_function(Some(CellularDistanceFunction::Euclidean));
+    noise.set_cellular_return_type(Some(CellularReturnType::Distance2Sub));
+    noise.set_frequency(Some(1.0));
+    noise
+});
+
+// Relieve del normal mapping procedural (0 = superficie lisa)
+const ROCKY_BUMP_STRENGTH: f32 = 0.6;
+const ASTEROID_BUMP_STRENGTH: f32 = 0.9;
+// Escala del ruido de relieve y paso usado para estimar su derivada
+const BUMP_ZOOM: f32 = 100.0;
+const BUMP_EPSILON: f32 = 0.01;
+
+// Lava del planeta de fuego: velocidad del flujo (unidades de ruido por tick)
+// y valor de cresta a partir del cual una grieta brilla
+const LAVA_FLOW_SPEED: f32 = 0.4;
+const LAVA_VEIN_THRESHOLD: f32 = 0.95;
+
+// Océano del planeta de agua: velocidad de las olas (unidades de ruido por
+// tick), umbral del ruido a partir del cual hay tierra y reflejo del sol
+const WATER_WAVE_SPEED: f32 = 0.15;
+const WATER_LAND_THRESHOLD: f32 = 0.45;
+const WATER_SHININESS: f32 = 64.0;
+const WATER_SPECULAR: f32 = 0.8;
+
+// Planeta de cristal: pasos de la rejilla de normales por eje (menos = facetas más grandes)
+const CRYSTAL_FACETS: f32 = 3.0;
+
+// Capa de nubes: escala del ruido en espacio del objeto, deriva por tick,
+// umbral a partir del cual aparecen nubes, ancho del borde suave y opacidad
+// máxima (0-255)
+const CLOUD_SHELL_ZOOM: f32 = 250.0;
+const CLOUD_DRIFT_SPEED: f32 = 0.3;
+const CLOUD_THRESHOLD: f32 = 0.1;
+const CLOUD_SOFTNESS: f32 = 0.4;
+const CLOUD_MAX_ALPHA: f32 = 220.0;
+
+// Oclusión ambiental aproximada: cuánto se oscurecen las grietas (0 = nada, 1 = negro)
+const AO_STRENGTH: f32 = 0.5;
+
 pub fn fragment_shader(fragment: &Fragment, uniforms: &Uniforms, planet_type: &PlanetType) -> Color {
-    match planet_type {
+    // Relieve procedural o facetas: se reemplaza la normal antes de calcular la luz
+    let bump = bump_strength(planet_type);
+    let perturbed;
+    let fragment = if matches!(planet_type, PlanetType::CrystalPlanet) {
+        perturbed = Fragment { normal: faceted_normal(fragment, uniforms), ..*fragment };
+        &perturbed
+    } else if bump > 0.0 {
+        perturbed = Fragment { normal: bumped_normal(fragment, uniforms, bump), ..*fragment };
+        &perturbed
+    } else {
+        fragment
+    };
+
+    // Modo de depuración: la normal en espacio mundo (tras el relieve) se
+    // mapea de [-1, 1] a [0, 1] por componente; +X rojo, +Y verde, +Z azul
+    if uniforms.show_normals {
+        let n = fragment.normal;
+        return Color::from_float((n.x + 1.0) / 2.0, (n.y + 1.0) / 2.0, (n.z + 1.0) / 2.0);
+    }
+
+    // El sol emite su propia luz, así que no se ilumina con las luces de la escena
+    let (diffuse, light_tint) = match planet_type {
+        PlanetType::Sun | PlanetType::Ring => (fragment.intensity, Color::new(255, 255, 255)),
+        _ => diffuse_lighting(fragment, uniforms),
+    };
+    let diffuse = diffuse * ambient_occlusion(fragment, uniforms, planet_type);
+    let diffuse = if uniforms.toon_shading { toon_quantize(diffuse) } else { diffuse };
+    let fragment = &Fragment { intensity: diffuse, ..*fragment };
+
+    // Contorno oscuro en la silueta para el modo toon
+    if uniforms.toon_shading
+        && !matches!(planet_type, PlanetType::Ring | PlanetType::CloudShell)
+        && dot(&fragment.normal, &view_direction(fragment, uniforms)) < TOON_OUTLINE_THRESHOLD
+    {
+        return Color::new(10, 10, 15);
+    }
+
+    let base_color = match planet_type {
         PlanetType::Sun => sun_shader(fragment, uniforms),
         PlanetType::RockyPlanet => rocky_planet_shader(fragment, uniforms),
         PlanetType::Earth => {
-            let earth_color = earth_shader(fragment, uniforms);
-            let cloud_color = cloud_shader(fragment, uniforms);
-            blend_layers(earth_color, cloud_color)
+            let earth_color = match &uniforms.earth_texture {
+                Some(texture) => textured_earth_shader(fragment, texture),
+                None => earth_shader(fragment, uniforms),
+            };
+            earth_day_night(fragment, uniforms, earth_color)
         },
         PlanetType::CrystalPlanet => crystal_planet_shader(fragment, uniforms),
         PlanetType::FirePlanet => fire_planet_shader(fragment, uniforms),
         PlanetType::WaterPlanet => water_planet_shader(fragment, uniforms),
         PlanetType::CloudPlanet => cloud_planet_shader(fragment, uniforms),
-        PlanetType::Moon => moon_shader(fragment, uniforms),
-        PlanetType::Asteroid => asteroid_shader(fragment, uniforms),
-        _ => Color::new(0, 0, 0),
+        PlanetType::Moon => apply_craters(fragment, uniforms, moon_shader(fragment, uniforms)),
+        PlanetType::Asteroid => apply_craters(fragment, uniforms, asteroid_shader(fragment, uniforms)),
+        PlanetType::RingedPlanet => gaseous_planet_shader(fragment, uniforms),
+        PlanetType::GasGiant => gas_giant_shader(fragment, uniforms),
+        PlanetType::IcePlanet => ice_planet_shader(fragment, uniforms),
+        PlanetType::Ring => return ring_shader(fragment, uniforms),
+        PlanetType::CloudShell => return cloud_shell_shader(fragment, uniforms),
+    };
+
+    // Los términos se combinan en float y se redondean una sola vez
+    let lit = base_color.to_vec3()
+        .component_mul(&light_tint.to_vec3())
+        .component_mul(&fragment.color.to_vec3());
+    Color::from_vec3(
+        lit + specular_highlight(fragment, uniforms, planet_type).to_vec3()
+            + atmosphere_rim(fragment, uniforms, planet_type).to_vec3()
+    )
+}
+
+pub fn bump_strength(planet_type: &PlanetType) -> f32 {
+    match planet_type {
+        PlanetType::RockyPlanet => ROCKY_BUMP_STRENGTH,
+        PlanetType::Asteroid => ASTEROID_BUMP_STRENGTH,
+        _ => 0.0,
+    }
+}
+
+// Redondea la normal en espacio del objeto a una rejilla de CRYSTAL_FACETS
+// pasos por eje, de modo que zonas enteras comparten la misma normal y se
+// ven como planos que reflejan la luz de golpe. Se vuelve al mundo con la
+// misma matriz de normales que usa el vertex shader.
+fn faceted_normal(fragment: &Fragment, uniforms: &Uniforms) -> Vec3 {
+    let model_mat3 = mat4_to_mat3(&uniforms.model_matrix);
+    let object_normal = (model_mat3.transpose() * fragment.normal).normalize();
+    let facet = (object_normal * CRYSTAL_FACETS).map(|c| c.round()) / CRYSTAL_FACETS;
+    if facet.magnitude() < 1e-6 {
+        return fragment.normal;
     }
+
+    let normal_matrix = model_mat3.transpose().try_inverse().unwrap_or(Mat3::identity());
+    (normal_matrix * facet).normalize()
 }
 
-// Función para mezclar capas de color
-fn blend_layers(base_color: Color, overlay_color: Color) -> Color {
-    base_color.lerp(&overlay_color, 0.5) // Mezcla 50% de cada color
+// Factor de oclusión en [1 - AO_STRENGTH, 1]. Reutiliza la altura del relieve:
+// donde el ruido es bajo hay grietas y llega menos luz. Solo para superficies
+// rocosas; el sol es emisivo y no debe oscurecerse.
+fn ambient_occlusion(fragment: &Fragment, uniforms: &Uniforms, planet_type: &PlanetType) -> f32 {
+    match planet_type {
+        PlanetType::RockyPlanet | PlanetType::Asteroid => {
+            let height = (bump_height(uniforms, &fragment.object_position) + 1.0) * 0.5;
+            1.0 - AO_STRENGTH * (1.0 - height.clamp(0.0, 1.0))
+        }
+        _ => 1.0,
+    }
 }
 
-fn cloud_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
-  let zoom = 100.0;  // to move our values 
-  let ox = 100.0; // offset x in the noise map
-  let oy = 100.0;
-  let x = fragment.vertex_position.x;
-  let y = fragment.vertex_position.y;
-  let t = uniforms.time as f32 * 0.5;
+// Altura del relieve en espacio del objeto, para que no se deslice al girar
+fn bump_height(uniforms: &Uniforms, position: &Vec3) -> f32 {
+    uniforms.noise.get_noise_3d(position.x * BUMP_ZOOM, position.y * BUMP_ZOOM, position.z * BUMP_ZOOM)
+}
 
-  let noise_value = uniforms.noise.get_noise_2d(x * zoom + ox + t, y * zoom + oy);
+// Perturba la normal con la derivada del ruido de altura.
+// La base TBN se reconstruye por fragmento: N es la normal interpolada, T la
+// tangente interpolada ortogonalizada contra N (Gram-Schmidt) y B = N × T.
+// La derivada se estima por diferencias finitas moviendo la posición del
+// objeto a lo largo de T y B (llevados al espacio del objeto con la inversa
+// de la matriz del modelo); la normal en espacio tangente (-du, -dv, 1) se
+// pasa al mundo con la TBN.
+fn bumped_normal(fragment: &Fragment, uniforms: &Uniforms, strength: f32) -> Vec3 {
+    let normal = fragment.normal.normalize();
+    let tangent = fragment.tangent - normal * dot(&normal, &fragment.tangent);
+    if tangent.magnitude() < 1e-6 {
+        return normal;
+    }
+    let tangent = tangent.normalize();
+    let bitangent = normal.cross(&tangent);
 
-  // Define cloud threshold and colors
-  let cloud_threshold = 0.5; // Adjust this value to change cloud density
-  let cloud_color = Color::new(255, 255, 255); // White for clouds
-  let sky_color = Color::new(30, 97, 145); // Sky blue
+    let to_object = mat4_to_mat3(&uniforms.model_matrix).try_inverse().unwrap_or(Mat3::identity());
+    let tangent_object = (to_object * tangent).normalize();
+    let bitangent_object = (to_object * bitangent).normalize();
 
-  // Determine if the pixel is part of a cloud or sky
-  let noise_color = if noise_value > cloud_threshold {
-    cloud_color
-  } else {
-    sky_color
-  };
+    let position = fragment.object_position;
+    let height = bump_height(uniforms, &position);
+    let du = (bump_height(uniforms, &(position + tangent_object * BUMP_EPSILON)) - height) / BUMP_EPSILON;
+    let dv = (bump_height(uniforms, &(position + bitangent_object * BUMP_EPSILON)) - height) / BUMP_EPSILON;
+
+    // La derivada del ruido ronda 10 por unidad; el 0.1 la deja en pendientes moderadas
+    let tangent_space = Vec3::new(-du * strength * 0.1, -dv * strength * 0.1, 1.0).normalize();
+    (tangent * tangent_space.x + bitangent * tangent_space.y + normal * tangent_space.z).normalize()
+}
+
+// Depresiones circulares con el fondo oscuro y un borde claro. Se muestrea en
+// la posición del objeto (sin `time`), así que los cráteres no se mueven.
+fn apply_craters(fragment: &Fragment, uniforms: &Uniforms, color: Color) -> Color {
+    let p = (fragment.object_position + seed_offset(uniforms.seed)) * CRATER_DENSITY;
+    // Dos escalas: cráteres grandes y otros más pequeños y numerosos
+    let large = crater_profile(CRATER_NOISE.get_noise_3d(p.x, p.y, p.z) + 1.0);
+    let small = crater_profile(CRATER_NOISE.get_noise_3d(p.x * 2.3 + 17.0, p.y * 2.3, p.z * 2.3) + 1.0);
+    let shade = 1.0 + large + small * 0.6;
+    color * shade
+}
+
+// Coordenadas equirectangulares a partir de una normal en espacio del objeto
+// (en una esfera unitaria, la propia posición): u es la longitud, de 0 a 1
+// empezando en -X y girando hacia -Z, y v la latitud, 0 en el polo norte (+Y)
+// y 1 en el sur. u salta de 1 a 0 en la costura, así que quien muestree debe
+// repetir en u; en los polos la longitud no está definida y u vale 0.5.
+pub fn sphere_uv(normal: Vec3) -> Vec2 {
+    let length = normal.magnitude();
+    if length < 1e-6 {
+        return Vec2::new(0.5, 0.5);
+    }
+    let n = normal / length;
+    let u = 0.5 + n.z.atan2(n.x) / (2.0 * PI);
+    let v = 0.5 - n.y.clamp(-1.0, 1.0).asin() / PI;
+    Vec2::new(u.rem_euclid(1.0), v)
+}
+
+// Los ruidos estáticos (cráteres, grietas) no se pueden volver a sembrar por
+// cuerpo, así que la semilla desplaza el punto de muestreo. La semilla 0 no
+// desplaza nada.
+fn seed_offset(seed: u64) -> Vec3 {
+    let hash = seed.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    let component = |shift: u32| ((hash >> shift) & 0xFFFF) as f32 * 0.01;
+    Vec3::new(component(0), component(16), component(32))
+}
+
+// Variación de brillo según la distancia al centro del cráter (en celdas):
+// negativa dentro, positiva en el borde y cero fuera
+fn crater_profile(distance: f32) -> f32 {
+    if distance < CRATER_RADIUS {
+        let t = distance / CRATER_RADIUS;
+        -CRATER_DEPTH * (1.0 - t * t)
+    } else if distance < CRATER_RADIUS * 1.3 {
+        let t = (distance - CRATER_RADIUS) / (CRATER_RADIUS * 0.3);
+        CRATER_RIM_BRIGHTNESS * (1.0 - (2.0 * t - 1.0).abs())
+    } else {
+        0.0
+    }
+}
+
+fn toon_quantize(intensity: f32) -> f32 {
+    TOON_BANDS.iter()
+        .find(|(threshold, _)| intensity >= *threshold)
+        .map(|(_, level)| *level)
+        .unwrap_or(TOON_SHADOW)
+}
+
+// Posición del fragmento en el espacio del mundo
+fn world_position(fragment: &Fragment, uniforms: &Uniforms) -> Vec3 {
+    let position = uniforms.model_matrix * Vec4::new(
+        fragment.object_position.x,
+        fragment.object_position.y,
+        fragment.object_position.z,
+        1.0
+    );
+    Vec3::new(position.x, position.y, position.z)
+}
+
+// Dirección desde el fragmento hacia la cámara. Cambia en cada punto de la
+// esfera: con la cámara cerca, la silueta no es donde la normal es
+// perpendicular a la dirección hacia el centro del cuerpo.
+fn view_direction(fragment: &Fragment, uniforms: &Uniforms) -> Vec3 {
+    let to_camera = uniforms.camera_position - world_position(fragment, uniforms);
+    if to_camera.magnitude() > 0.0 {
+        to_camera.normalize()
+    } else {
+        Vec3::new(0.0, 0.0, 1.0)
+    }
+}
+
+// Dirección desde el fragmento hacia una luz
+fn light_direction(light: &Light, position: &Vec3) -> Vec3 {
+    let to_light = light.position - position;
+    if to_light.magnitude() > 0.0 {
+        to_light.normalize()
+    } else {
+        Vec3::new(0.0, 0.0, 1.0)
+    }
+}
+
+// Suma la luz difusa de todas las fuentes; devuelve la intensidad total y el
+// color promedio de las luces ponderado por su aporte
+fn diffuse_lighting(fragment: &Fragment, uniforms: &Uniforms) -> (f32, Color) {
+    let position = world_position(fragment, uniforms);
+    let mut total = 0.0;
+    let mut tint = Color::new(255, 255, 255);
+
+    for light in &uniforms.lights {
+        let contribution = dot(&fragment.normal, &light_direction(light, &position)).max(0.0) * light.intensity;
+        if contribution > 0.0 {
+            total += contribution;
+            tint = tint.lerp(&light.color, contribution / total);
+        }
+    }
+
+    (AMBIENT_LIGHT + total, tint)
+}
+
+// Atmósfera de cada planeta: (color, intensidad, exponente del borde)
+fn rim_params(planet_type: &PlanetType) -> Option<(Color, f32, f32)> {
+    match planet_type {
+        PlanetType::Earth => Some((Color::new(80, 150, 255), 0.9, 3.0)),
+        PlanetType::WaterPlanet => Some((Color::new(100, 200, 255), 0.8, 3.0)),
+        PlanetType::CloudPlanet => Some((Color::new(220, 235, 255), 0.6, 2.5)),
+        PlanetType::FirePlanet => Some((Color::new(255, 140, 40), 0.9, 2.5)),
+        PlanetType::RingedPlanet => Some((Color::new(230, 200, 150), 0.5, 3.0)),
+        PlanetType::GasGiant => Some((Color::new(240, 210, 170), 0.4, 3.0)),
+        PlanetType::IcePlanet => Some((Color::new(200, 230, 255), 0.5, 3.5)),
+        // Fresnel fuerte: los bordes del cristal reflejan casi todo
+        PlanetType::CrystalPlanet => Some((Color::new(235, 220, 255), 0.8, 2.5)),
+        _ => None,
+    }
+}
+
+// Borde de Fresnel que ilumina la silueta para simular la atmósfera
+fn atmosphere_rim(fragment: &Fragment, uniforms: &Uniforms, planet_type: &PlanetType) -> Color {
+    match rim_params(planet_type) {
+        Some((rim_color, intensity, power)) => {
+            let facing = dot(&fragment.normal, &view_direction(fragment, uniforms)).clamp(0.0, 1.0);
+            let rim = (1.0 - facing).powf(power);
+            rim_color * (rim * intensity)
+        }
+        None => Color::black(),
+    }
+}
+
+// Brillo especular (shininess, intensidad) de cada tipo de planeta
+fn specular_params(planet_type: &PlanetType) -> (f32, f32) {
+    match planet_type {
+        PlanetType::Sun | PlanetType::Ring | PlanetType::CloudShell => (1.0, 0.0), // Sin reflejos
+        // El planeta de agua calcula su reflejo en el shader, solo sobre el océano
+        PlanetType::WaterPlanet => (1.0, 0.0),
+        PlanetType::CrystalPlanet => (128.0, 1.0),
+        PlanetType::IcePlanet => (ICE_SHININESS, 1.0),
+        PlanetType::Earth => (32.0, 0.4),
+        PlanetType::CloudPlanet => (16.0, 0.2),
+        _ => (8.0, 0.1),
+    }
+}
+
+fn specular_highlight(fragment: &Fragment, uniforms: &Uniforms, planet_type: &PlanetType) -> Color {
+    let (shininess, strength) = specular_params(planet_type);
+    if strength <= 0.0 {
+        return Color::black();
+    }
+    phong_specular(fragment, uniforms, shininess, strength)
+}
+
+// Término especular de Phong sumado sobre todas las luces
+fn phong_specular(fragment: &Fragment, uniforms: &Uniforms, shininess: f32, strength: f32) -> Color {
+    let normal = fragment.normal;
+    let position = world_position(fragment, uniforms);
+    let view_dir = view_direction(fragment, uniforms);
+    let mut highlight = Color::black();
+
+    for light in &uniforms.lights {
+        let light_dir = light_direction(light, &position);
+        if dot(&normal, &light_dir) <= 0.0 {
+            continue; // Lado no iluminado por esta luz
+        }
+
+        let reflect_dir = normal * (2.0 * dot(&normal, &light_dir)) - light_dir;
+        let specular = dot(&reflect_dir, &view_dir).max(0.0).powf(shininess);
+        highlight = highlight + light.color * (specular * strength * light.intensity);
+    }
+
+    highlight
+}
 
-  noise_color * fragment.intensity
+// Nubes de la capa exterior: blancas, iluminadas como la superficie y
+// transparentes donde no hay nube, para que se vea el suelo debajo. El ruido
+// es 3D sobre la posición en el objeto, así que las nubes giran con el
+// planeta y además se desplazan despacio con el tiempo.
+fn cloud_shell_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
+    let p = fragment.object_position * CLOUD_SHELL_ZOOM;
+    let drift = uniforms.time as f32 * CLOUD_DRIFT_SPEED;
+    let density = uniforms.noise.get_noise_3d(p.x + drift, p.y, p.z + drift * 0.5);
+
+    let coverage = smoothstep(CLOUD_THRESHOLD, CLOUD_THRESHOLD + CLOUD_SOFTNESS, density);
+    let color = Color::new(255, 255, 255) * fragment.intensity.min(1.0);
+    color.with_alpha((coverage * CLOUD_MAX_ALPHA) as u8)
+}
+
+// Anillos con bandas concéntricas y transparencia parcial
+fn ring_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
+    let radial = fragment.tex_coords.x; // 0 en el borde interior, 1 en el exterior
+    let angle = fragment.tex_coords.y;
+
+    // Bandas concéntricas con algo de ruido para que no sean perfectas
+    let band_noise = uniforms.noise.get_noise_2d(radial * 400.0, angle * 20.0);
+    let bands = ((radial * 40.0).sin() * 0.5 + 0.5) * 0.7 + band_noise.abs() * 0.3;
+
+    let light_band = Color::new(230, 210, 170); // Crema
+    let dark_band = Color::new(150, 120, 80);   // Marrón claro
+    let color = dark_band.lerp(&light_band, bands);
+
+    // Huecos entre bandas más transparentes
+    let alpha = (80.0 + bands * 150.0).clamp(0.0, 255.0) as u8;
+    color.with_alpha(alpha)
 }
 
 fn gaseous_planet_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
     let zoom = 50.0;
-    let x = fragment.vertex_position.x;
-    let y = fragment.vertex_position.y;
+    let x = fragment.object_position.x;
+    let y = fragment.object_position.y;
 
     // Generar ruido para simular nubes gaseosas
     let noise_value = uniforms.noise.get_noise_2d(x * zoom, y * zoom);
@@ -114,19 +508,6 @@ fn gaseous_planet_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
     // Crear un patrón de franjas utilizando ruido y la posición y
     let stripe_pattern = (y * 3.0).sin() * 0.5 + 0.5; // Aumentar la frecuencia para más franjas
 
-    // Mezclar colores según el patrón de franjas
-    let base_color = if stripe_pattern < 0.2 {
-        stripe_color1
-    } else if stripe_pattern < 0.4 {
-        stripe_color2
-    } else if stripe_pattern < 0.6 {
-        stripe_color3
-    } else if stripe_pattern < 0.8 {
-        stripe_color4
-    } else {
-        stripe_color5 // Añadir un color gris claro para más variación
-    };
-
     // Interpolación suave entre colores
     let smooth_color = if stripe_pattern < 0.2 {
         stripe_color1.lerp(&stripe_color2, stripe_pattern * 5.0)
@@ -140,6 +521,10 @@ fn gaseous_planet_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
         stripe_color5.lerp(&stripe_color1, (stripe_pattern - 0.8) * 5.0)
     };
 
+    // Bandas finas más claras y más oscuras; al trabajar en HSL el tono se conserva
+    let band_shift = (y * 14.0 + noise_value * 0.8).sin() * 0.06;
+    let smooth_color = smooth_color.adjust_lightness(band_shift);
+
     // Ajustar el tamaño de la mancha blanca y hacerla más difusa
     let white_spot_size = 0.05; // Tamaño de la mancha blanca más pequeña
     let white_spot = if (y.abs() - 0.5).abs() < white_spot_size {
@@ -159,10 +544,64 @@ fn gaseous_planet_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
     final_color * fragment.intensity * 0.8 // Reducir la intensidad para un efecto más sutil
 }
 
+// Bandas de latitud estilo Júpiter: la coordenada y del objeto, deformada con
+// FBm, recorre una paleta crema/canela/marrón. El tiempo desplaza el ruido
+// lentamente para que las bandas fluyan, y una mancha ovalada rojiza hace de
+// "gran mancha".
+fn gas_giant_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
+    let p = fragment.object_position;
+    let t = uniforms.time as f32 * 0.002;
+
+    let turbulence = uniforms.noise.get_noise_3d(p.x * 3.0 + t, p.y * 6.0, p.z * 3.0 - t);
+    let latitude = p.y + turbulence * GAS_TURBULENCE;
+
+    let cream = Color::new(240, 225, 190);
+    let tan = Color::new(210, 175, 130);
+    let brown = Color::new(150, 100, 65);
+
+    // Dos frecuencias de bandas para que no se vean todas iguales
+    let band = (latitude * GAS_BAND_COUNT * PI).sin() * 0.5 + 0.5;
+    let fine_band = (latitude * GAS_BAND_COUNT * 3.1 * PI).sin() * 0.5 + 0.5;
+    let band_color = if band < 0.5 {
+        cream.lerp(&tan, band * 2.0)
+    } else {
+        tan.lerp(&brown, (band - 0.5) * 2.0)
+    };
+    let band_color = band_color.lerp(&cream, fine_band * 0.2);
+
+    // Gran mancha: elipse en (u, latitud) con el borde difuminado
+    let uv = sphere_uv(p);
+    let spot_distance = (((uv.x - 0.63) / 0.07).powi(2) + ((latitude + 0.3) / 0.12).powi(2)).sqrt();
+    let spot_color = Color::new(190, 95, 60).lerp(&Color::new(220, 150, 110), turbulence.abs() * 2.0);
+    let color = band_color.lerp(&spot_color, 1.0 - smoothstep(0.7, 1.0, spot_distance));
+
+    color * fragment.intensity
+}
+
+// Hielo entre azul profundo y casi blanco, con grietas finas y oscuras. El
+// brillo especular intenso lo añade specular_highlight con ICE_SHININESS.
+fn ice_planet_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
+    let p = fragment.object_position;
+    let zoom = 60.0;
+
+    let deep_ice = Color::new(70, 130, 190);
+    let snow = Color::new(235, 245, 255);
+    let noise_value = uniforms.noise.get_noise_3d(p.x * zoom, p.y * zoom, p.z * zoom) * 0.5 + 0.5;
+    let ice_color = deep_ice.lerp(&snow, noise_value * 0.8);
+
+    // Grietas: F2 - F1 (desplazado de [-1, ...] a [0, ...]) cerca de cero
+    let q = (p + seed_offset(uniforms.seed)) * 5.0;
+    let crack_distance = CRACK_NOISE.get_noise_3d(q.x, q.y, q.z) + 1.0;
+    let crack = 1.0 - smoothstep(0.0, ICE_CRACK_WIDTH, crack_distance);
+    let color = ice_color.lerp(&Color::new(30, 50, 80), crack * 0.8);
+
+    color * fragment.intensity
+}
+
 fn rocky_planet_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
   let zoom = 30.0;
-  let x = fragment.vertex_position.x;
-  let y = fragment.vertex_position.y;
+  let x = fragment.object_position.x;
+  let y = fragment.object_position.y;
 
   // Generar múltiples capas de ruido para textura rocosa detallada
   let noise_value = uniforms.noise.get_noise_2d(x * zoom, y * zoom);                    // Ruido grande para formaciones rocosas
@@ -238,12 +677,9 @@ fn sun_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
   let bright_color = Color::new(255, 240, 0); // yellow
   let dark_color = Color::new(211, 84, 0);   //Burnt orange
 
-  // Get fragment position
-  let position = Vec3::new(
-    fragment.vertex_position.x,
-    fragment.vertex_position.y,
-    fragment.depth
-  );
+  // Get fragment position in object space. Using the screen depth for z made
+  // the spots slide whenever the camera or the sun moved.
+  let position = fragment.object_position;
 
   // Base frequency and amplitude for the pulsating effect
   let base_frequency = 0.2;
@@ -275,8 +711,8 @@ fn sun_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
 
 fn moon_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
     let zoom = 100.0;
-    let x = fragment.vertex_position.x;
-    let y = fragment.vertex_position.y;
+    let x = fragment.object_position.x;
+    let y = fragment.object_position.y;
 
     // Generar ruido para simular la superficie lunar
     let noise_value = uniforms.noise.get_noise_2d(x * zoom, y * zoom);
@@ -297,10 +733,17 @@ fn moon_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
     rotated_color * fragment.intensity
 }
 
+// Solo la superficie, desde la textura equirectangular; las nubes las dibuja
+// aparte la pasada de la capa de nubes, igual que con el shader procedural
+fn textured_earth_shader(fragment: &Fragment, texture: &Texture) -> Color {
+    let uv = sphere_uv(fragment.object_position);
+    texture.sample(uv.x, uv.y) * fragment.intensity
+}
+
 fn earth_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
     let zoom = 30.0; // Zoom para la textura de la Tierra
-    let x = fragment.vertex_position.x;
-    let y = fragment.vertex_position.y;
+    let x = fragment.object_position.x;
+    let y = fragment.object_position.y;
 
     // Generar ruido para simular la textura de la Tierra
     let noise_value = uniforms.noise.get_noise_2d(x * zoom, y * zoom);
@@ -325,20 +768,55 @@ fn earth_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
         Color::new(0, 0, 0) // Sin isla
     };
 
-    // Aplicar el shader de nubes
-    let cloud_color = cloud_shader(fragment, uniforms);
+    // Bruma azul de la atmósfera; las nubes van en su propia capa. Se ilumina
+    // junto con el resto al final, no por separado.
+    let haze_color = Color::new(30, 97, 145);
 
-    // Mezclar el color base con el color de las nubes y la isla
-    let final_color = base_color.lerp(&cloud_color, 0.5).lerp(&island_effect, 0.5); // Mezcla 50% de nubes y 50% de isla
+    // Mezclar el color base con la bruma y la isla
+    let final_color = base_color.lerp(&haze_color, 0.5).lerp(&island_effect, 0.5); // Mezcla 50% de bruma y 50% de isla
 
     final_color * fragment.intensity
 }
 
 
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+// Lado de día y de noche de la Tierra según la dirección hacia el sol.
+// `day_color` ya viene iluminado por la luz difusa, así que aquí solo se
+// mezcla con el lado de noche.
+fn earth_day_night(fragment: &Fragment, uniforms: &Uniforms, day_color: Color) -> Color {
+    let position = world_position(fragment, uniforms);
+    let sun_dot = uniforms.lights.iter()
+        .map(|light| dot(&fragment.normal, &light_direction(light, &position)))
+        .fold(-1.0, f32::max);
+
+    // Banda suave en el terminador en lugar de una línea dura
+    let day_factor = smoothstep(-0.15, 0.25, sun_dot);
+
+    // Luces de ciudades: puntos dispersos tomados del ruido de alta frecuencia
+    let zoom = 400.0;
+    let x = fragment.object_position.x;
+    let y = fragment.object_position.y;
+    let city_noise = uniforms.noise.get_noise_2d(x * zoom, y * zoom);
+
+    let night_color = Color::new(5, 10, 40); // Azul oscuro
+    let city_light_color = Color::new(255, 200, 100); // Amarillo cálido
+    let night_side = if city_noise > 0.6 {
+        night_color.lerp(&city_light_color, (city_noise - 0.6) * 2.5)
+    } else {
+        night_color
+    };
+
+    night_side.lerp(&day_color, day_factor)
+}
+
 fn cloud_planet_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
     let zoom = 50.0; // Controla la escala del ruido
-    let x = fragment.vertex_position.x;
-    let y = fragment.vertex_position.y;
+    let x = fragment.object_position.x;
+    let y = fragment.object_position.y;
     let t = uniforms.time as f32 * 0.5; // Tiempo para animar las nubes
 
     // Generar múltiples capas de ruido para simular nubes
@@ -374,113 +852,87 @@ fn cloud_planet_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
 }
 
 fn crystal_planet_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
-    let zoom = 30.0;
-    let x = fragment.vertex_position.x;
-    let y = fragment.vertex_position.y;
-
-    // Generar ruido para simular cristales brillantes
-    let noise_value = uniforms.noise.get_noise_2d(x * zoom + uniforms.time as f32 * 0.2, y * zoom);
-    
-    // Colores base para los cristales
-    let crystal_color1 = Color::new(0, 255, 255); // Cian
-    let crystal_color2 = Color::new(255, 0, 255); // Magenta
-
-    // Interpolación suave para simular el brillo de los cristales
-    let color = crystal_color1.lerp(&crystal_color2, noise_value);
-
-    // Aumentar el brillo
-    let bright_color = color * 1.5; // Aumentar el brillo
-
-    // Ajustar la intensidad del color final
-    bright_color * fragment.intensity * 2.8 // Reducir la intensidad para un efecto más sutil
+    // La normal ya viene facetada; en espacio del objeto cada faceta tiene
+    // una dirección fija, así que su tono no cambia al girar el planeta
+    let facet = (mat4_to_mat3(&uniforms.model_matrix).transpose() * fragment.normal).normalize();
+
+    // Tono entre cian y magenta según la dirección de la faceta, con un
+    // toque del ruido celular y una deriva lenta en el tiempo
+    let position = fragment.object_position * 30.0;
+    let cell = uniforms.noise.get_noise_3d(position.x, position.y, position.z);
+    let drift = uniforms.time as f32 * 0.2;
+    let hue = 180.0 + 120.0 * (0.5 + 0.5 * (facet.x * 3.0 + facet.y * 2.0 + facet.z + cell + drift * 0.05).sin());
+    let color = Color::from_hsv(hue, 0.6, 1.0);
+
+    // Caras poco iluminadas quedan muy oscuras: contraste alto entre facetas
+    color * fragment.intensity.powf(1.5)
 }
 
 fn fire_planet_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
-    let zoom = 80.0;
-    let x = fragment.vertex_position.x;
-    let y = fragment.vertex_position.y;
-
-    // Generar ruido para simular fuego con movimiento
-    let noise_value = uniforms.noise.get_noise_2d(x * zoom + uniforms.time as f32 * 0.5, y * zoom);
-    
-    // Colores base para el fuego
-    let fire_color1 = Color::new(255, 140, 0); // Naranja
-    let fire_color2 = Color::new(255, 0, 0);   // Rojo
-
-    // Interpolación suave para simular el movimiento del fuego
-    let color = fire_color1.lerp(&fire_color2, noise_value);
-
-    // Aplicar el shader de franjas como capa extra
-    let stripe_color = striped_planet_shader(fragment, uniforms);
-    
-    // Ajustar la opacidad del shader de franjas
-    let opacity = 0.5; // Ajustar la opacidad según sea necesario
-    let final_color = color.lerp(&stripe_color, opacity);
-
-    // Ajustar la intensidad del color final
-    final_color * fragment.intensity
+    let zoom = 1200.0;
+    let position = fragment.object_position * zoom;
+
+    // Se desplaza el dominio del ruido con el tiempo para que las grietas fluyan
+    let flow = uniforms.time as f32 * LAVA_FLOW_SPEED;
+    let noise_value = uniforms.noise.get_noise_3d(position.x + flow, position.y, position.z + flow * 0.5);
+
+    // Corteza de basalto, un poco más clara donde el ruido es alto
+    let basalt_dark = Color::new(25, 18, 16);
+    let basalt_light = Color::new(70, 50, 40);
+    let crust = basalt_dark.lerp(&basalt_light, (noise_value + 1.0) * 0.5);
+
+    // Las vetas siguen el cruce por cero del ruido; el umbral estrecho las
+    // deja finas y con un borde marcado
+    let ridge = 1.0 - noise_value.abs();
+    let vein = smoothstep(LAVA_VEIN_THRESHOLD, LAVA_VEIN_THRESHOLD + 0.02, ridge);
+    let lava_orange = Color::new(255, 90, 0);
+    let lava_yellow = Color::new(255, 220, 80);
+    let lava = lava_orange.lerp(&lava_yellow, smoothstep(LAVA_VEIN_THRESHOLD + 0.02, 1.0, ridge));
+
+    // La corteza se ilumina con el sol; la lava es emisiva y se ve también
+    // en el lado nocturno
+    crust * fragment.intensity + lava * vein
 }
 
 fn water_planet_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
-  let zoom = 40.0;
-  let x = fragment.vertex_position.x;
-  let y = fragment.vertex_position.y;
-
-  // Crear un seed basado en la posición
-  let seed = (x * 1000.0 + y * 1000.0) as u64; 
-  let mut rng = StdRng::seed_from_u64(seed);
-  let random_offset = rng.gen_range(0.0..=1.0); // Generar un desplazamiento aleatorio
-
-  // Generar ruido para simular agua con movimiento
-  let noise_value = uniforms.noise.get_noise_2d(x * zoom + random_offset, y * zoom + (uniforms.time as f32 * 0.1).sin());
-
-  // Generar ondas con mayor amplitud utilizando una función seno controlada
-  let wave_effect = ((x + uniforms.time as f32 * 0.1).sin() * 0.5 + (y + uniforms.time as f32 * 0.1).cos() * 0.5).sin() * 1.0; // Aumentar la amplitud de la ola
-
-  // Colores base para el agua, con un celeste más saturado y profundo
-  let water_color1 = Color::new(0, 0, 255);     // Azul profundo
-  let water_color2 = Color::new(0, 150, 255);   // Celeste intenso
-
-  // Interpolación suave para simular el movimiento del agua
-  let wave_intensity = wave_effect * noise_value.abs() * 1.5; // Aumentar la intensidad de la ola
-
-  // Lerp entre los dos colores usando la intensidad de la ola
-  let color = water_color1.lerp(&water_color2, wave_intensity);
-
-  // Ajustar la intensidad del color final
-  color * fragment.intensity * 0.9 // Aumentar ligeramente la intensidad para resaltar más el celeste
+    let position = fragment.object_position;
+    let flow = uniforms.time as f32 * WATER_WAVE_SPEED;
+
+    // Dos capas de oleaje a distinta escala que se desplazan en direcciones
+    // distintas, así los brillos se mueven sin repetirse
+    let large = position * 300.0;
+    let small = position * 900.0;
+    let waves = uniforms.noise.get_noise_3d(large.x + flow, large.y, large.z - flow) * 0.6
+        + uniforms.noise.get_noise_3d(small.x - flow * 1.7, small.y + flow, small.z) * 0.4;
+
+    // Azul profundo a cian según el oleaje, con las crestas más claras
+    let deep_water = Color::new(0, 20, 120);
+    let shallow_water = Color::new(0, 170, 220);
+    let foam = Color::new(200, 240, 255);
+    let ocean = deep_water.lerp(&shallow_water, (waves + 1.0) * 0.5)
+        .lerp(&foam, smoothstep(0.35, 0.6, waves) * 0.3);
+
+    // Continentes tenues: ruido de baja frecuencia (sin `time`, no se mueven)
+    // desplazado para no coincidir con las olas
+    let land_position = position * 120.0 + Vec3::new(500.0, 500.0, 500.0);
+    let land_noise = uniforms.noise.get_noise_3d(land_position.x, land_position.y, land_position.z);
+    let land = smoothstep(WATER_LAND_THRESHOLD, WATER_LAND_THRESHOLD + 0.05, land_noise);
+    let coast_color = Color::new(40, 110, 40); // Verde
+    let inland_color = Color::new(180, 160, 110); // Tostado
+    let land_color = coast_color.lerp(&inland_color, smoothstep(WATER_LAND_THRESHOLD + 0.1, WATER_LAND_THRESHOLD + 0.4, land_noise));
+
+    let surface = ocean.lerp(&land_color, land * 0.8);
+
+    // Reflejo del sol solo sobre el agua
+    surface * fragment.intensity
+        + phong_specular(fragment, uniforms, WATER_SHININESS, WATER_SPECULAR) * (1.0 - land)
 }
 
 
-fn striped_planet_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
-    let zoom = 10.0; // Controla la frecuencia de las franjas
-    let x = fragment.vertex_position.x;
-    let y = fragment.vertex_position.y;
-
-    // Colores base para las franjas
-    let stripe_color1 = Color::new(255, 100, 0); // Naranja intenso
-    let stripe_color2 = Color::new(255, 200, 0); // Amarillo brillante
-    let stripe_color3 = Color::new(255, 50, 0);  // Naranja rojizo
-
-    // Crear un patrón de franjas utilizando una función seno
-    let stripe_pattern = (y * zoom + uniforms.time as f32 * 0.1).sin() + (x * zoom * 0.5).sin(); // Movimiento más lento
-
-    // Determinar el color basado en el patrón de franjas
-    let color = if stripe_pattern > 0.0 {
-        stripe_color1
-    } else {
-        stripe_color3 // Cambiar a naranja rojizo
-    };
-
-    // Ajustar la intensidad del color final y aplicar la opacidad
-    let opacity = (stripe_pattern.abs() * 0.5).clamp(0.0, 1.0); // Controlar la opacidad
-    color * opacity * fragment.intensity
-}
-
 pub fn asteroid_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
     let zoom = 20.0; // Controla la escala del ruido
-    let x = fragment.vertex_position.x;
-    let y = fragment.vertex_position.y;
+    let x = fragment.object_position.x;
+    let y = fragment.object_position.y;
 
     // Generar múltiples capas de ruido para textura detallada
     let base_noise = uniforms.noise.get_noise_2d(x * zoom, y * zoom); // Ruido base
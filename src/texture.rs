@@ -0,0 +1,67 @@
+use image::error::{LimitError, LimitErrorKind};
+use image::{ImageError, RgbImage};
+use crate::color::Color;
+
+// RGB image sampled with normalized coordinates. u wraps around (for
+// equirectangular maps whose left and right edges meet at the seam) and v is
+// clamped, so the poles reuse the first and last rows.
+pub struct Texture {
+    width: usize,
+    height: usize,
+    texels: Vec<Color>,
+}
+
+impl Texture {
+    pub fn load(path: &str) -> Result<Self, ImageError> {
+        Self::from_image(image::open(path)?.to_rgb8())
+    }
+
+    // An empty image has no texel to fall back on, so it is rejected here
+    // instead of panicking later in `texel`
+    fn from_image(image: RgbImage) -> Result<Self, ImageError> {
+        if image.width() == 0 || image.height() == 0 {
+            return Err(ImageError::Limits(LimitError::from_kind(LimitErrorKind::DimensionError)));
+        }
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        let texels = image.pixels().map(|p| Color::new(p[0], p[1], p[2])).collect();
+        Ok(Texture { width, height, texels })
+    }
+
+    // Bilinear interpolation between the four nearest texel centers
+    pub fn sample(&self, u: f32, v: f32) -> Color {
+        let x = u * self.width as f32 - 0.5;
+        let y = v * self.height as f32 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+
+        let top = self.texel(x0 as i64, y0 as i64).lerp(&self.texel(x0 as i64 + 1, y0 as i64), fx);
+        let bottom = self.texel(x0 as i64, y0 as i64 + 1).lerp(&self.texel(x0 as i64 + 1, y0 as i64 + 1), fx);
+        top.lerp(&bottom, fy)
+    }
+
+    fn texel(&self, x: i64, y: i64) -> Color {
+        let x = x.rem_euclid(self.width as i64) as usize;
+        let y = y.clamp(0, self.height as i64 - 1) as usize;
+        self.texels[y * self.width + x]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_images_are_rejected() {
+        for (width, height) in [(0, 4), (4, 0), (0, 0)] {
+            assert!(Texture::from_image(RgbImage::new(width, height)).is_err(), "{}x{}", width, height);
+        }
+    }
+
+    #[test]
+    fn single_texel_image_samples_everywhere() {
+        let texture = Texture::from_image(RgbImage::from_pixel(1, 1, image::Rgb([10, 20, 30]))).unwrap();
+        for (u, v) in [(0.0, 0.0), (0.5, 0.5), (1.0, 1.0), (-0.3, 1.7)] {
+            assert_eq!(texture.sample(u, v), Color::new(10, 20, 30));
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use std::time::Instant;
+
+const SAMPLE_COUNT: usize = 30;
+
+// Measures frame durations and keeps a moving average over the last
+// SAMPLE_COUNT frames so the displayed FPS doesn't jitter
+pub struct FrameTimer {
+    last_frame: Instant,
+    samples: [f32; SAMPLE_COUNT],
+    index: usize,
+    count: usize,
+    sum: f32,
+}
+
+impl Default for FrameTimer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FrameTimer {
+    pub fn new() -> Self {
+        FrameTimer {
+            last_frame: Instant::now(),
+            samples: [0.0; SAMPLE_COUNT],
+            index: 0,
+            count: 0,
+            sum: 0.0,
+        }
+    }
+
+    // Records the end of a frame and returns its duration in seconds
+    pub fn tick(&mut self) -> f32 {
+        let now = Instant::now();
+        let delta = now.duration_since(self.last_frame).as_secs_f32();
+        self.last_frame = now;
+
+        self.sum += delta - self.samples[self.index];
+        self.samples[self.index] = delta;
+        self.index = (self.index + 1) % SAMPLE_COUNT;
+        self.count = (self.count + 1).min(SAMPLE_COUNT);
+
+        delta
+    }
+
+    pub fn average_frame_time(&self) -> f32 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.sum / self.count as f32
+        }
+    }
+
+    pub fn fps(&self) -> f32 {
+        let frame_time = self.average_frame_time();
+        if frame_time > 0.0 { 1.0 / frame_time } else { 0.0 }
+    }
+}
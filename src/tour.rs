@@ -0,0 +1,149 @@
+use nalgebra_glm::Vec3;
+use crate::camera::CameraPreset;
+use crate::CelestialBody;
+
+// Segundos que la cámara se queda en cada parada y que tarda en ir a la siguiente
+const TOUR_HOLD: f32 = 4.0;
+const TOUR_TRAVEL: f32 = 3.0;
+
+// Parada del recorrido: la cámara mira a un cuerpo (o al origen con None)
+// desde `eye_offset`. El desplazamiento es relativo al cuerpo, así que la
+// cámara lo acompaña en su órbita.
+pub struct Keyframe {
+  target: Option<usize>,
+  eye_offset: Vec3,
+}
+
+// Recorrido automático para el modo `--demo`: se detiene TOUR_HOLD segundos
+// en cada parada, viaja a la siguiente con aceleración suave y al terminar
+// vuelve a empezar
+pub struct Tour {
+  keyframes: Vec<Keyframe>,
+}
+
+impl Tour {
+  // Una vista general del sistema y luego cada cuerpo que orbita, en el
+  // orden de la escena. La distancia depende de la escala del cuerpo.
+  pub fn visiting(celestial_bodies: &[CelestialBody]) -> Self {
+    let mut keyframes = vec![Keyframe { target: None, eye_offset: Vec3::new(0.0, 35.0, 60.0) }];
+    for (index, body) in celestial_bodies.iter().enumerate() {
+      if body.orbit_radius > 0.0 {
+        keyframes.push(Keyframe {
+          target: Some(index),
+          eye_offset: Vec3::new(0.0, 1.5, 6.0) * body.scale,
+        });
+      }
+    }
+    Tour { keyframes }
+  }
+
+  // Duración de una vuelta completa, en segundos
+  pub fn duration(&self) -> f32 {
+    self.keyframes.len() as f32 * (TOUR_HOLD + TOUR_TRAVEL)
+  }
+
+  // Estado de la cámara en el instante `time` del reloj de simulación.
+  // `positions` son las posiciones actuales de los cuerpos, en el mismo
+  // orden que la escena con la que se creó el recorrido.
+  pub fn sample(&self, time: f32, positions: &[Vec3]) -> CameraPreset {
+    let step = TOUR_HOLD + TOUR_TRAVEL;
+    let time = time.rem_euclid(self.duration());
+    let index = ((time / step) as usize).min(self.keyframes.len() - 1);
+    let local = time - index as f32 * step;
+
+    let current = &self.keyframes[index];
+    let next = &self.keyframes[(index + 1) % self.keyframes.len()];
+    let t = if local < TOUR_HOLD {
+      0.0
+    } else {
+      let t = (local - TOUR_HOLD) / TOUR_TRAVEL;
+      t * t * (3.0 - 2.0 * t)
+    };
+
+    let focus = |keyframe: &Keyframe| {
+      keyframe.target
+        .and_then(|target| positions.get(target).copied())
+        .unwrap_or(Vec3::new(0.0, 0.0, 0.0))
+    };
+    let center = focus(current).lerp(&focus(next), t);
+    let eye_offset = current.eye_offset.lerp(&next.eye_offset, t);
+
+    CameraPreset {
+      eye: center + eye_offset,
+      center,
+      up: Vec3::new(0.0, 1.0, 0.0),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::planet::PlanetType;
+
+  fn body(orbit_radius: f32, scale: f32) -> CelestialBody {
+    CelestialBody {
+      position: Vec3::new(orbit_radius, 0.0, 0.0),
+      local_position: Vec3::new(orbit_radius, 0.0, 0.0),
+      parent: None,
+      scale,
+      rotation: Vec3::new(0.0, 0.0, 0.0),
+      shader_type: PlanetType::RockyPlanet,
+      orbit_radius,
+      orbit_speed: 1.0,
+      orbit_angle: 0.0,
+      rotation_speed: 0.0,
+      axial_tilt: 0.0,
+      seed: 0,
+    }
+  }
+
+  // Un sol que no orbita y dos planetas: la vista general y dos paradas
+  fn tour_and_positions() -> (Tour, Vec<Vec3>) {
+    let bodies = [body(0.0, 2.0), body(10.0, 1.0), body(20.0, 0.5)];
+    let positions = bodies.iter().map(|body| body.position).collect();
+    (Tour::visiting(&bodies), positions)
+  }
+
+  fn assert_same_view(a: &CameraPreset, b: &CameraPreset) {
+    assert!((a.eye - b.eye).magnitude() < 1e-4 && (a.center - b.center).magnitude() < 1e-4, "{:?} != {:?}", a, b);
+  }
+
+  #[test]
+  fn visits_only_orbiting_bodies() {
+    let (tour, _) = tour_and_positions();
+    assert_eq!(tour.keyframes.len(), 3);
+    assert_eq!(tour.keyframes[1].target, Some(1));
+    assert_eq!(tour.keyframes[2].eye_offset, Vec3::new(0.0, 1.5, 6.0) * 0.5);
+    assert_eq!(tour.duration(), 3.0 * (TOUR_HOLD + TOUR_TRAVEL));
+  }
+
+  #[test]
+  fn holds_each_keyframe_then_arrives_at_the_next() {
+    let (tour, positions) = tour_and_positions();
+    let overview = CameraPreset { eye: Vec3::new(0.0, 35.0, 60.0), center: Vec3::new(0.0, 0.0, 0.0), up: Vec3::new(0.0, 1.0, 0.0) };
+    assert_same_view(&tour.sample(0.0, &positions), &overview);
+    assert_same_view(&tour.sample(TOUR_HOLD * 0.99, &positions), &overview);
+
+    let first_planet = tour.sample(TOUR_HOLD + TOUR_TRAVEL, &positions);
+    assert_eq!(first_planet.center, positions[1]);
+    assert_eq!(first_planet.eye, positions[1] + Vec3::new(0.0, 1.5, 6.0));
+
+    // A mitad del viaje el suavizado vale justo 0.5
+    let halfway = tour.sample(TOUR_HOLD + TOUR_TRAVEL / 2.0, &positions);
+    assert!((halfway.center - positions[1] / 2.0).magnitude() < 1e-4);
+  }
+
+  #[test]
+  fn wraps_around_after_the_last_keyframe() {
+    let (tour, positions) = tour_and_positions();
+    let duration = tour.duration();
+    for time in [0.0, 1.0, 5.5, 12.0] {
+      assert_same_view(&tour.sample(time + duration, &positions), &tour.sample(time, &positions));
+      assert_same_view(&tour.sample(time - duration, &positions), &tour.sample(time, &positions));
+    }
+
+    // El último viaje termina de vuelta en la vista general
+    assert_same_view(&tour.sample(duration - 1e-3, &positions), &tour.sample(0.0, &positions));
+  }
+}
@@ -0,0 +1,133 @@
+use nalgebra_glm::{Vec3, Mat4, look_at, ortho};
+use std::f32::consts::PI;
+
+// Escala uniforme, luego rotación (primero X, después Y y por último Z) y
+// finalmente traslación
+pub fn model(translation: Vec3, scale: f32, rotation: Vec3) -> Mat4 {
+    let (sin_x, cos_x) = rotation.x.sin_cos();
+    let (sin_y, cos_y) = rotation.y.sin_cos();
+    let (sin_z, cos_z) = rotation.z.sin_cos();
+
+    let rotation_matrix_x = Mat4::new(
+        1.0,  0.0,    0.0,   0.0,
+        0.0,  cos_x, -sin_x, 0.0,
+        0.0,  sin_x,  cos_x, 0.0,
+        0.0,  0.0,    0.0,   1.0,
+    );
+
+    let rotation_matrix_y = Mat4::new(
+        cos_y,  0.0,  sin_y, 0.0,
+        0.0,    1.0,  0.0,   0.0,
+        -sin_y, 0.0,  cos_y, 0.0,
+        0.0,    0.0,  0.0,   1.0,
+    );
+
+    let rotation_matrix_z = Mat4::new(
+        cos_z, -sin_z, 0.0, 0.0,
+        sin_z,  cos_z, 0.0, 0.0,
+        0.0,    0.0,  1.0, 0.0,
+        0.0,    0.0,  0.0, 1.0,
+    );
+
+    let rotation_matrix = rotation_matrix_z * rotation_matrix_y * rotation_matrix_x;
+
+    let transform_matrix = Mat4::new(
+        scale, 0.0,   0.0,   translation.x,
+        0.0,   scale, 0.0,   translation.y,
+        0.0,   0.0,   scale, translation.z,
+        0.0,   0.0,   0.0,   1.0,
+    );
+
+    transform_matrix * rotation_matrix
+}
+
+pub fn view(eye: Vec3, center: Vec3, up: Vec3) -> Mat4 {
+    look_at(&eye, &center, &up)
+}
+
+// `fov` es el campo de visión vertical en grados
+pub fn perspective(fov: f32, width: f32, height: f32, near: f32, far: f32) -> Mat4 {
+    // Una ventana minimizada puede reportar altura 0
+    let aspect_ratio = width / height.max(1.0);
+
+    // nalgebra-glm recibe primero el aspecto y después el FOV vertical
+    nalgebra_glm::perspective(aspect_ratio, fov * PI / 180.0, near, far)
+}
+
+pub fn orthographic(width: f32, height: f32, near: f32, far: f32) -> Mat4 {
+    // Mitad de la altura visible en unidades del mundo
+    let half_height = 20.0;
+    let half_width = half_height * width / height.max(1.0);
+
+    ortho(-half_width, half_width, -half_height, half_height, near, far)
+}
+
+// De NDC a píxeles: x de -1 a 1 va de 0 a `width`, y de 1 a -1 va de 0 a
+// `height` (la y de pantalla crece hacia abajo) y z se conserva
+pub fn viewport(width: f32, height: f32) -> Mat4 {
+    Mat4::new(
+        width / 2.0, 0.0, 0.0, width / 2.0,
+        0.0, -height / 2.0, 0.0, height / 2.0,
+        0.0, 0.0, 1.0, 0.0,
+        0.0, 0.0, 0.0, 1.0
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra_glm::Vec4;
+
+    fn apply(matrix: &Mat4, point: Vec3) -> Vec3 {
+        let p = matrix * Vec4::new(point.x, point.y, point.z, 1.0);
+        Vec3::new(p.x, p.y, p.z) / p.w
+    }
+
+    fn assert_close(actual: Vec3, expected: Vec3) {
+        assert!((actual - expected).magnitude() < 1e-4, "{:?} != {:?}", actual, expected);
+    }
+
+    #[test]
+    fn model_scales_rotates_then_translates() {
+        let translation = Vec3::new(3.0, 4.0, 5.0);
+        assert_close(apply(&model(translation, 2.0, Vec3::zeros()), Vec3::new(1.0, 0.0, 0.0)), Vec3::new(5.0, 4.0, 5.0));
+        assert_close(apply(&model(translation, 2.0, Vec3::zeros()), Vec3::zeros()), translation);
+
+        // Un cuarto de vuelta en Y lleva +x a -z
+        assert_close(apply(&model(Vec3::zeros(), 1.0, Vec3::new(0.0, PI / 2.0, 0.0)), Vec3::new(1.0, 0.0, 0.0)), Vec3::new(0.0, 0.0, -1.0));
+        // Primero X (+y pasa a +z) y después Y (+z pasa a +x)
+        assert_close(apply(&model(Vec3::zeros(), 1.0, Vec3::new(PI / 2.0, PI / 2.0, 0.0)), Vec3::new(0.0, 1.0, 0.0)), Vec3::new(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn view_puts_the_eye_at_the_origin_looking_down_negative_z() {
+        let matrix = view(Vec3::new(0.0, 0.0, 5.0), Vec3::zeros(), Vec3::new(0.0, 1.0, 0.0));
+        assert_close(apply(&matrix, Vec3::new(0.0, 0.0, 5.0)), Vec3::zeros());
+        assert_close(apply(&matrix, Vec3::zeros()), Vec3::new(0.0, 0.0, -5.0));
+        assert_close(apply(&matrix, Vec3::new(1.0, 2.0, 0.0)), Vec3::new(1.0, 2.0, -5.0));
+
+        // Desde la derecha, el origen queda delante y +z a la izquierda
+        let matrix = view(Vec3::new(5.0, 0.0, 0.0), Vec3::zeros(), Vec3::new(0.0, 1.0, 0.0));
+        assert_close(apply(&matrix, Vec3::new(0.0, 0.0, 1.0)), Vec3::new(-1.0, 0.0, -5.0));
+    }
+
+    #[test]
+    fn perspective_maps_the_frustum_edges_to_ndc() {
+        // 90° verticales en una ventana el doble de ancha que de alta
+        let matrix = perspective(90.0, 200.0, 100.0, 1.0, 100.0);
+        assert_close(apply(&matrix, Vec3::new(0.0, 0.0, -1.0)), Vec3::new(0.0, 0.0, -1.0));
+        assert_close(apply(&matrix, Vec3::new(0.0, 0.0, -100.0)), Vec3::new(0.0, 0.0, 1.0));
+        // A 10 de distancia se ven 10 hacia arriba y 20 hacia la derecha
+        let (top, right) = (apply(&matrix, Vec3::new(0.0, 10.0, -10.0)), apply(&matrix, Vec3::new(20.0, 0.0, -10.0)));
+        assert!(top.x.abs() < 1e-4 && (top.y - 1.0).abs() < 1e-4, "{:?}", top);
+        assert!((right.x - 1.0).abs() < 1e-4 && right.y.abs() < 1e-4, "{:?}", right);
+    }
+
+    #[test]
+    fn viewport_maps_ndc_corners_to_pixel_corners() {
+        let matrix = viewport(800.0, 600.0);
+        assert_close(apply(&matrix, Vec3::new(-1.0, 1.0, 0.5)), Vec3::new(0.0, 0.0, 0.5));
+        assert_close(apply(&matrix, Vec3::new(1.0, -1.0, 0.5)), Vec3::new(800.0, 600.0, 0.5));
+        assert_close(apply(&matrix, Vec3::new(0.0, 0.0, -1.0)), Vec3::new(400.0, 300.0, -1.0));
+    }
+}
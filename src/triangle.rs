@@ -1,71 +1,150 @@
-use nalgebra_glm::{Vec3, dot, Vec2};
+use std::ops::Range;
+use nalgebra_glm::{Vec3, dot};
 use crate::fragment::Fragment;
 use crate::vertex::{self, Vertex};
-use crate::color::Color;
 
-pub fn triangle(v1: &Vertex, v2: &Vertex, v3: &Vertex) -> Vec<Fragment> {
-  let mut fragments = Vec::new();
+// Rotated-grid sub-sample positions inside a pixel, used for coverage AA
+const COVERAGE_SAMPLES: [(f32, f32); 4] = [(0.375, 0.125), (0.875, 0.375), (0.125, 0.625), (0.625, 0.875)];
+
+// With `coverage_aa` each pixel is tested at COVERAGE_SAMPLES instead of its
+// center: fragments record the covered fraction in `coverage` and are shaded
+// once, at the centroid of the covered samples so attributes never come from
+// outside the triangle. Fragments are appended to `fragments`, so one buffer
+// can be reused for a whole mesh.
+pub fn triangle(v1: &Vertex, v2: &Vertex, v3: &Vertex, width: usize, height: usize, coverage_aa: bool, fragments: &mut Vec<Fragment>) {
+  triangle_in_rows(v1, v2, v3, width, 0..height, coverage_aa, fragments);
+}
+
+// Like `triangle`, but only emits fragments whose pixel row is in `rows`.
+// Each pixel is computed independently, so the fragments are the same ones
+// (in the same order) that `triangle` emits for those rows.
+pub fn triangle_in_rows(v1: &Vertex, v2: &Vertex, v3: &Vertex, width: usize, rows: Range<usize>, coverage_aa: bool, fragments: &mut Vec<Fragment>) {
   let (a, b, c) = (v1.transformed_position, v2.transformed_position, v3.transformed_position);
 
-  let (min_x, min_y, max_x, max_y) = calculate_bounding_box(&a, &b, &c);
+  // Only pixels inside the triangle's screen bounding box (clamped to the framebuffer) are tested
+  let (min_x, min_y, max_x, max_y) = calculate_bounding_box(&a, &b, &c, width, rows);
 
   let light_dir = Vec3::new(0.0, 0.0, 1.0);
 
   let triangle_area = edge_function(&a, &b, &c);
 
+  // Top-left fill rule: a pixel center exactly on an edge only belongs to the
+  // triangle if that edge is a top or left edge, so shared edges are drawn once
+  let top_left = [
+    is_top_left(&b, &c, triangle_area),
+    is_top_left(&c, &a, triangle_area),
+    is_top_left(&a, &b, triangle_area),
+  ];
+
+  // Check if a point is inside the triangle
+  let covers = |w: f32, top_left: bool| w > 0.0 || (w == 0.0 && top_left);
+  let inside = |point: &Vec3| {
+    let (w1, w2, w3) = barycentric_coordinates(point, &a, &b, &c, triangle_area);
+    covers(w1, top_left[0]) && covers(w2, top_left[1]) && covers(w3, top_left[2])
+  };
+
   // Iterate over each pixel in the bounding box
   for y in min_y..=max_y {
     for x in min_x..=max_x {
-      let point = Vec3::new(x as f32 + 0.5, y as f32 + 0.5, 0.0);
+      let (point, coverage) = if coverage_aa {
+        let mut covered = 0;
+        let mut centroid = Vec3::new(0.0, 0.0, 0.0);
+        for (dx, dy) in COVERAGE_SAMPLES {
+          let sample = Vec3::new(x as f32 + dx, y as f32 + dy, 0.0);
+          if inside(&sample) {
+            covered += 1;
+            centroid += sample;
+          }
+        }
+        if covered == 0 {
+          continue;
+        }
+        (centroid / covered as f32, covered as f32 / COVERAGE_SAMPLES.len() as f32)
+      } else {
+        let center = Vec3::new(x as f32 + 0.5, y as f32 + 0.5, 0.0);
+        if !inside(&center) {
+          continue;
+        }
+        (center, 1.0)
+      };
 
       // Calculate barycentric coordinates
       let (w1, w2, w3) = barycentric_coordinates(&point, &a, &b, &c, triangle_area);
 
-      // Check if the point is inside the triangle
-      if w1 >= 0.0 && w1 <= 1.0 && 
-         w2 >= 0.0 && w2 <= 1.0 &&
-         w3 >= 0.0 && w3 <= 1.0 {
-
-        // Interpolate normal
-        let normal = v1.transformed_normal * w1 + v2.transformed_normal * w2 + v3.transformed_normal * w3;
-        let normal = normal.normalize();
-
-        // Calculate lighting intensity
-        let intensity = dot(&normal, &light_dir).max(0.0);
+      // Perspective-correct weights: interpolate attribute/w and divide by interpolated 1/w
+      let (p1, p2, p3) = (w1 * v1.inv_w, w2 * v2.inv_w, w3 * v3.inv_w);
+      let inv_w_sum = p1 + p2 + p3;
+      let (p1, p2, p3) = (p1 / inv_w_sum, p2 / inv_w_sum, p3 / inv_w_sum);
 
-        // Create a gray color (unchanged)
-        let color = Color::new(100, 100, 100); // Medium gray
+      // Interpolate every vertex attribute (normal, tangent, color, UVs, object position)
+      let interpolated = Vertex::barycentric(v1, v2, v3, p1, p2, p3);
+      let normal = interpolated.transformed_normal.normalize();
 
-        // Interpolate depth
-        let depth = a.z * w1 + b.z * w2 + c.z * w3;
+      // Calculate lighting intensity
+      let intensity = dot(&normal, &light_dir).max(0.0);
 
-        // Positions of the original vertex
-        let vertex_position = v1.position * w1 + v2.position * w2 + v3.position * w3;
+      // Interpolate depth (NDC z is already divided by w, so it is linear in screen space)
+      let depth = a.z * w1 + b.z * w2 + c.z * w3;
 
-        fragments.push(Fragment::new(
-            Vec2::new(x as f32, y as f32),
-            color,
-            depth,
-            normal,
-            intensity,
-            vertex_position,
-        ));
-      }
+      let mut fragment = Fragment::new(
+          Vec3::new(x as f32, y as f32, depth),
+          normal,
+          interpolated.position,
+          interpolated.tex_coords,
+          interpolated.color,
+          intensity,
+      );
+      // The tangent is re-orthogonalized against the normal in the fragment shader
+      fragment.tangent = interpolated.transformed_tangent;
+      fragment.coverage = coverage;
+      fragments.push(fragment);
     }
   }
+}
+
+// Fragment located at a vertex, for shading once per vertex (Gouraud). Uses
+// the same intensity as `triangle` so both paths light a surface alike.
+pub fn vertex_fragment(vertex: &Vertex) -> Fragment {
+  let position = vertex.transformed_position;
+  let normal = vertex.transformed_normal.normalize();
+  let intensity = dot(&normal, &Vec3::new(0.0, 0.0, 1.0)).max(0.0);
+  let mut fragment = Fragment::new(
+      position,
+      normal,
+      vertex.position,
+      vertex.tex_coords,
+      vertex.color,
+      intensity,
+  );
+  fragment.tangent = vertex.transformed_tangent;
+  fragment
+}
 
-  fragments
+// Back-facing triangles wind clockwise in NDC, which the viewport's y flip
+// turns into a non-positive signed area in screen space.
+pub fn is_back_facing(v1: &Vertex, v2: &Vertex, v3: &Vertex) -> bool {
+  let (a, b, c) = (v1.transformed_position, v2.transformed_position, v3.transformed_position);
+  edge_function(&a, &b, &c) <= 0.0
 }
 
-fn calculate_bounding_box(v1: &Vec3, v2: &Vec3, v3: &Vec3) -> (i32, i32, i32, i32) {
-    let min_x = v1.x.min(v2.x).min(v3.x).floor() as i32;
-    let min_y = v1.y.min(v2.y).min(v3.y).floor() as i32;
-    let max_x = v1.x.max(v2.x).max(v3.x).ceil() as i32;
-    let max_y = v1.y.max(v2.y).max(v3.y).ceil() as i32;
+fn calculate_bounding_box(v1: &Vec3, v2: &Vec3, v3: &Vec3, width: usize, rows: Range<usize>) -> (i32, i32, i32, i32) {
+    let min_x = (v1.x.min(v2.x).min(v3.x).floor() as i32).max(0);
+    let min_y = (v1.y.min(v2.y).min(v3.y).floor() as i32).max(rows.start as i32);
+    let max_x = (v1.x.max(v2.x).max(v3.x).ceil() as i32).min(width as i32 - 1);
+    let max_y = (v1.y.max(v2.y).max(v3.y).ceil() as i32).min(rows.end as i32 - 1);
 
     (min_x, min_y, max_x, max_y)
 }
 
+// With y pointing down and a positive signed area, a top edge is horizontal
+// and runs right to left, and a left edge runs downwards. Clockwise triangles
+// (only drawn when culling is off) see their edges reversed.
+fn is_top_left(from: &Vec3, to: &Vec3, area: f32) -> bool {
+    let (dx, dy) = (to.x - from.x, to.y - from.y);
+    let (dx, dy) = if area < 0.0 { (-dx, -dy) } else { (dx, dy) };
+    (dy == 0.0 && dx < 0.0) || dy > 0.0
+}
+
 fn barycentric_coordinates(p: &Vec3, a: &Vec3, b: &Vec3, c: &Vec3, area: f32) -> (f32, f32, f32) {
     let w1 = edge_function(b, c, p) / area;
     let w2 = edge_function(c, a, p) / area;
@@ -79,3 +158,169 @@ fn edge_function(a: &Vec3, b: &Vec3, c: &Vec3) -> f32 {
 }
 
 
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use nalgebra_glm::Vec2;
+
+  const SIZE: usize = 8;
+
+  // Vertex already in screen space, as the rasterizer receives it
+  fn screen_vertex(x: f32, y: f32) -> Vertex {
+    let mut vertex = Vertex::new(Vec3::new(x, y, 0.0), Vec3::new(0.0, 0.0, 1.0), Vec2::new(0.0, 0.0));
+    vertex.transformed_position = Vec3::new(x, y, 0.5);
+    vertex
+  }
+
+  // How many fragments land on each pixel of a SIZE × SIZE framebuffer
+  fn coverage_counts(triangles: &[[Vertex; 3]]) -> Vec<u32> {
+    let mut fragments = Vec::new();
+    for [v1, v2, v3] in triangles {
+      triangle(v1, v2, v3, SIZE, SIZE, false, &mut fragments);
+    }
+    let mut counts = vec![0; SIZE * SIZE];
+    for fragment in &fragments {
+      counts[fragment.position.y as usize * SIZE + fragment.position.x as usize] += 1;
+    }
+    counts
+  }
+
+  // The quad covers the whole framebuffer, and both of its diagonals go
+  // through pixel centers (k + 0.5, k + 0.5) or (k + 0.5, SIZE - k - 0.5), so
+  // the shared edge decides who owns a whole row of pixels
+  fn split_quads() -> [[[Vertex; 3]; 2]; 2] {
+    let s = SIZE as f32;
+    let (top_left, top_right) = (screen_vertex(0.0, 0.0), screen_vertex(s, 0.0));
+    let (bottom_left, bottom_right) = (screen_vertex(0.0, s), screen_vertex(s, s));
+    [
+      [
+        [top_left.clone(), bottom_left.clone(), bottom_right.clone()],
+        [top_left.clone(), bottom_right.clone(), top_right.clone()],
+      ],
+      [
+        [top_left.clone(), bottom_left.clone(), top_right.clone()],
+        [top_right, bottom_left, bottom_right],
+      ],
+    ]
+  }
+
+  #[test]
+  fn shared_edge_is_written_exactly_once() {
+    for halves in split_quads() {
+      for half in &halves {
+        assert!(!is_back_facing(&half[0], &half[1], &half[2]));
+      }
+      let counts = coverage_counts(&halves);
+      assert!(counts.iter().all(|&count| count == 1), "{:?}", counts);
+    }
+  }
+
+  // With culling off, clockwise triangles reach the rasterizer with a
+  // negative area and must follow the same rule
+  #[test]
+  fn shared_edge_is_written_exactly_once_when_clockwise() {
+    for halves in split_quads() {
+      let reversed = halves.map(|[v1, v2, v3]| [v1, v3, v2]);
+      for half in &reversed {
+        assert!(edge_function(&half[0].transformed_position, &half[1].transformed_position, &half[2].transformed_position) < 0.0);
+      }
+      let counts = coverage_counts(&reversed);
+      assert!(counts.iter().all(|&count| count == 1), "{:?}", counts);
+    }
+  }
+
+  // A quad seen at an angle: its left edge is at w = 1 and its right edge
+  // four times farther away, with u going from 0 to 1 across it
+  #[test]
+  fn uvs_are_perspective_correct() {
+    let s = SIZE as f32;
+    let far_w = 4.0;
+    let corner = |x: f32, y: f32, u: f32, w: f32| {
+      let mut vertex = screen_vertex(x, y);
+      vertex.tex_coords = Vec2::new(u, y / s);
+      vertex.inv_w = 1.0 / w;
+      vertex
+    };
+    let (top_left, bottom_left) = (corner(0.0, 0.0, 0.0, 1.0), corner(0.0, s, 0.0, 1.0));
+    let (top_right, bottom_right) = (corner(s, 0.0, 1.0, far_w), corner(s, s, 1.0, far_w));
+
+    let mut fragments = Vec::new();
+    triangle(&top_left, &bottom_left, &bottom_right, SIZE, SIZE, false, &mut fragments);
+    triangle(&top_left, &bottom_right, &top_right, SIZE, SIZE, false, &mut fragments);
+    assert_eq!(fragments.len(), SIZE * SIZE);
+
+    for fragment in &fragments {
+      // u / w and 1 / w are the ones that are linear in screen space
+      let t = (fragment.position.x + 0.5) / s;
+      let expected = t / far_w / ((1.0 - t) + t / far_w);
+      assert!((fragment.tex_coords.x - expected).abs() < 1e-4, "u {} at t {}, expected {}", fragment.tex_coords.x, t, expected);
+    }
+
+    // In the middle of the screen the affine value (0.5) would be far off
+    let middle = fragments.iter().find(|f| f.position.x == (SIZE / 2) as f32).unwrap();
+    assert!(middle.tex_coords.x < 0.3);
+  }
+
+  // NDC depth is already divided by w, so it stays linear in screen space even
+  // when the corners have different w: z(x, y) on the plane through the corners
+  #[test]
+  fn depth_is_linear_across_a_slanted_triangle() {
+    let plane = |x: f32, y: f32| 0.2 + 0.05 * x + 0.03 * y;
+    let s = SIZE as f32;
+    let corner = |x: f32, y: f32, w: f32| {
+      let mut vertex = screen_vertex(x, y);
+      vertex.transformed_position.z = plane(x, y);
+      vertex.inv_w = 1.0 / w;
+      vertex
+    };
+    let (a, b, c) = (corner(0.0, 0.0, 1.0), corner(0.0, s, 2.0), corner(s, s, 5.0));
+
+    let mut fragments = Vec::new();
+    triangle(&a, &b, &c, SIZE, SIZE, false, &mut fragments);
+    assert!(fragments.len() > SIZE * SIZE / 3);
+
+    for fragment in &fragments {
+      let expected = plane(fragment.position.x + 0.5, fragment.position.y + 0.5);
+      assert!((fragment.depth - expected).abs() < 1e-5, "depth {} at {:?}, expected {}", fragment.depth, fragment.position, expected);
+      assert_eq!(fragment.position.z, fragment.depth);
+    }
+  }
+
+  // A triangle counter-clockwise in NDC (facing the camera) goes through the
+  // viewport, whose y flip makes it clockwise on screen
+  #[test]
+  fn back_facing_follows_ndc_winding() {
+    let viewport = crate::transform::viewport(SIZE as f32, SIZE as f32);
+    let to_screen = |x: f32, y: f32| {
+      let screen = viewport * nalgebra_glm::Vec4::new(x, y, 0.0, 1.0);
+      screen_vertex(screen.x, screen.y)
+    };
+    let (a, b, c) = (to_screen(-0.5, -0.5), to_screen(0.5, -0.5), to_screen(0.0, 0.5));
+
+    assert!(!is_back_facing(&a, &b, &c));
+    assert!(!is_back_facing(&b, &c, &a));
+    assert!(is_back_facing(&a, &c, &b));
+    // Zero area is culled too
+    assert!(is_back_facing(&a, &b, &to_screen(0.0, -0.5)));
+  }
+
+  // A triangle about two pixels across, away from the origin, only produces
+  // fragments inside its own bounding box
+  #[test]
+  fn tiny_triangle_stays_within_its_bounding_box() {
+    let (a, b, c) = (screen_vertex(4.6, 3.1), screen_vertex(6.4, 3.4), screen_vertex(5.2, 4.9));
+    let (min_x, min_y, max_x, max_y) = calculate_bounding_box(&a.transformed_position, &b.transformed_position, &c.transformed_position, SIZE, 0..SIZE);
+    assert_eq!((min_x, min_y, max_x, max_y), (4, 3, 7, 5));
+
+    for coverage_aa in [false, true] {
+      let mut fragments = Vec::new();
+      triangle(&a, &b, &c, SIZE, SIZE, coverage_aa, &mut fragments);
+      assert!(!fragments.is_empty());
+      for fragment in &fragments {
+        let (x, y) = (fragment.position.x as i32, fragment.position.y as i32);
+        assert!((min_x..=max_x).contains(&x) && (min_y..=max_y).contains(&y), "fragment at ({}, {})", x, y);
+      }
+    }
+  }
+}
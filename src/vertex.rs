@@ -1,4 +1,4 @@
-use nalgebra_glm::{Vec2, Vec3};
+use nalgebra_glm::{Vec2, Vec3, Vec4};
 use crate::color::Color;
 
 #[derive(Clone, Debug)]
@@ -6,9 +6,14 @@ pub struct Vertex {
   pub position: Vec3,
   pub normal: Vec3,
   pub tex_coords: Vec2,
+  // Dirección +U de la textura, ortogonal a la normal (para normal mapping)
+  pub tangent: Vec3,
   pub color: Color,
   pub transformed_position: Vec3,
   pub transformed_normal: Vec3,
+  pub transformed_tangent: Vec3,
+  pub clip_position: Vec4,
+  pub inv_w: f32,
 }
 
 impl Vertex {
@@ -17,9 +22,13 @@ impl Vertex {
       position,
       normal,
       tex_coords,
-      color: Color::black(),
+      tangent: Vec3::new(1.0, 0.0, 0.0),
+      color: Color::new(255, 255, 255),
       transformed_position: position,
       transformed_normal: normal,
+      transformed_tangent: Vec3::new(1.0, 0.0, 0.0),
+      clip_position: Vec4::new(position.x, position.y, position.z, 1.0),
+      inv_w: 1.0,
     }
   }
 
@@ -28,9 +37,31 @@ impl Vertex {
       position,
       normal: Vec3::new(0.0, 0.0, 0.0),
       tex_coords: Vec2::new(0.0, 0.0),
+      tangent: Vec3::new(1.0, 0.0, 0.0),
       color,
       transformed_position: Vec3::new(0.0, 0.0, 0.0),
       transformed_normal: Vec3::new(0.0, 0.0, 0.0),
+      transformed_tangent: Vec3::new(1.0, 0.0, 0.0),
+      clip_position: Vec4::new(0.0, 0.0, 0.0, 1.0),
+      inv_w: 1.0,
+    }
+  }
+
+  // Weighted sum of every attribute of three vertices. The weights are used
+  // as given, so callers pass perspective-corrected weights when needed.
+  // Color is done as two lerps because Color only stores u8 channels.
+  pub fn barycentric(a: &Vertex, b: &Vertex, c: &Vertex, w0: f32, w1: f32, w2: f32) -> Vertex {
+    Vertex {
+      position: a.position * w0 + b.position * w1 + c.position * w2,
+      normal: a.normal * w0 + b.normal * w1 + c.normal * w2,
+      tex_coords: a.tex_coords * w0 + b.tex_coords * w1 + c.tex_coords * w2,
+      tangent: a.tangent * w0 + b.tangent * w1 + c.tangent * w2,
+      color: a.color.lerp(&b.color, w1 / (w0 + w1).max(f32::EPSILON)).lerp(&c.color, w2),
+      transformed_position: a.transformed_position * w0 + b.transformed_position * w1 + c.transformed_position * w2,
+      transformed_normal: a.transformed_normal * w0 + b.transformed_normal * w1 + c.transformed_normal * w2,
+      transformed_tangent: a.transformed_tangent * w0 + b.transformed_tangent * w1 + c.transformed_tangent * w2,
+      clip_position: a.clip_position * w0 + b.clip_position * w1 + c.clip_position * w2,
+      inv_w: a.inv_w * w0 + b.inv_w * w1 + c.inv_w * w2,
     }
   }
 
@@ -46,9 +77,69 @@ impl Default for Vertex {
       position: Vec3::new(0.0, 0.0, 0.0),
       normal: Vec3::new(0.0, 1.0, 0.0),
       tex_coords: Vec2::new(0.0, 0.0),
+      tangent: Vec3::new(1.0, 0.0, 0.0),
       color: Color::black(),
       transformed_position: Vec3::new(0.0, 0.0, 0.0),
       transformed_normal: Vec3::new(0.0, 1.0, 0.0),
+      transformed_tangent: Vec3::new(1.0, 0.0, 0.0),
+      clip_position: Vec4::new(0.0, 0.0, 0.0, 1.0),
+      inv_w: 1.0,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn sample_vertex(seed: f32, color: Color) -> Vertex {
+    let mut vertex = Vertex::new(Vec3::new(seed, seed * 2.0, -seed), Vec3::new(0.0, seed, 1.0), Vec2::new(seed * 0.1, 1.0 - seed * 0.1));
+    vertex.tangent = Vec3::new(1.0, seed, 0.0);
+    vertex.color = color;
+    vertex.set_transformed(Vec3::new(seed * 10.0, seed * 20.0, 0.5), Vec3::new(seed, 0.0, 1.0));
+    vertex.transformed_tangent = Vec3::new(0.0, 1.0, seed);
+    vertex.clip_position = Vec4::new(seed, -seed, 0.5, 1.0 + seed);
+    vertex.inv_w = 1.0 / (1.0 + seed);
+    vertex
+  }
+
+  fn colors() -> [Color; 3] {
+    [Color::new(255, 0, 40), Color::new(0, 255, 80), Color::new(30, 60, 255)]
+  }
+
+  #[test]
+  fn unit_weight_returns_that_vertex_exactly() {
+    let [c1, c2, c3] = colors();
+    let (v1, v2, v3) = (sample_vertex(1.0, c1), sample_vertex(2.5, c2), sample_vertex(-3.0, c3));
+
+    for (weights, expected) in [((1.0, 0.0, 0.0), &v1), ((0.0, 1.0, 0.0), &v2), ((0.0, 0.0, 1.0), &v3)] {
+      let result = Vertex::barycentric(&v1, &v2, &v3, weights.0, weights.1, weights.2);
+      assert_eq!(result.position, expected.position);
+      assert_eq!(result.normal, expected.normal);
+      assert_eq!(result.tex_coords, expected.tex_coords);
+      assert_eq!(result.tangent, expected.tangent);
+      assert_eq!(result.color, expected.color);
+      assert_eq!(result.transformed_position, expected.transformed_position);
+      assert_eq!(result.transformed_normal, expected.transformed_normal);
+      assert_eq!(result.transformed_tangent, expected.transformed_tangent);
+      assert_eq!(result.clip_position, expected.clip_position);
+      assert_eq!(result.inv_w, expected.inv_w);
+    }
+  }
+
+  // The two chained lerps must agree with the weighted sum of each channel,
+  // give or take the rounding of the intermediate u8 color
+  #[test]
+  fn color_matches_weighted_channels() {
+    let [c1, c2, c3] = colors();
+    let (v1, v2, v3) = (sample_vertex(0.0, c1), sample_vertex(0.0, c2), sample_vertex(0.0, c3));
+
+    for (w0, w1, w2) in [(0.25, 0.5, 0.25), (0.6, 0.1, 0.3), (0.0, 0.3, 0.7), (1.0 / 3.0, 1.0 / 3.0, 1.0 / 3.0)] {
+      let result = Vertex::barycentric(&v1, &v2, &v3, w0, w1, w2).color.to_vec3() * 255.0;
+      let expected = (c1.to_vec3() * w0 + c2.to_vec3() * w1 + c3.to_vec3() * w2) * 255.0;
+      for channel in 0..3 {
+        assert!((result[channel] - expected[channel]).abs() <= 1.0, "weights ({}, {}, {}): {:?} vs {:?}", w0, w1, w2, result, expected);
+      }
     }
   }
 }